@@ -0,0 +1,543 @@
+use std::collections::BTreeMap;
+
+use rand::rngs::StdRng;
+use crate::utils::*;
+use crate::graph::ID;
+use crate::link_queues::Message;
+use crate::sim::{Io, RoutingAlgorithm, TestPacket, Forward};
+
+/*
+* AODV like reactive routing.
+* Routes are only discovered when a packet needs to be forwarded.
+* The route request (RREQ) is flooded and creates reverse routes,
+* the route reply (RREP) travels back along the reverse route.
+* Broken links are reported with route errors (RERR).
+* Routes are compared by the summed link cost of the selected metric.
+*/
+
+const RREQ : u8 = 1;
+const RREP : u8 = 2;
+const RERR : u8 = 3;
+
+const MAX_TTL : u8 = 64;
+
+#[derive(Clone)]
+struct Rreq {
+	rreq_id: u32,
+	origin: ID,
+	origin_seqno: u32,
+	dest: ID,
+	// 0 if unknown
+	dest_seqno: u32,
+	hops: u8,
+	// summed link cost to the originator
+	cost: u32,
+	ttl: u8,
+}
+
+impl Rreq {
+	fn write(&self, payload: &mut Vec<u8>) {
+		write_u8(payload, RREQ);
+		write_u32(payload, self.rreq_id);
+		write_u32(payload, self.origin);
+		write_u32(payload, self.origin_seqno);
+		write_u32(payload, self.dest);
+		write_u32(payload, self.dest_seqno);
+		write_u8(payload, self.hops);
+		write_u32(payload, self.cost);
+		write_u8(payload, self.ttl);
+	}
+
+	fn read(payload: &[u8], pos: &mut usize) -> Option<Self> {
+		Some(Self {
+			rreq_id: read_u32(payload, pos)?,
+			origin: read_u32(payload, pos)?,
+			origin_seqno: read_u32(payload, pos)?,
+			dest: read_u32(payload, pos)?,
+			dest_seqno: read_u32(payload, pos)?,
+			hops: read_u8(payload, pos)?,
+			cost: read_u32(payload, pos)?,
+			ttl: read_u8(payload, pos)?,
+		})
+	}
+}
+
+#[derive(Clone)]
+struct Rrep {
+	// originator of the route request
+	origin: ID,
+	dest: ID,
+	dest_seqno: u32,
+	hops: u8,
+	// summed link cost to the destination
+	cost: u32,
+}
+
+impl Rrep {
+	fn write(&self, payload: &mut Vec<u8>) {
+		write_u8(payload, RREP);
+		write_u32(payload, self.origin);
+		write_u32(payload, self.dest);
+		write_u32(payload, self.dest_seqno);
+		write_u8(payload, self.hops);
+		write_u32(payload, self.cost);
+	}
+
+	fn read(payload: &[u8], pos: &mut usize) -> Option<Self> {
+		Some(Self {
+			origin: read_u32(payload, pos)?,
+			dest: read_u32(payload, pos)?,
+			dest_seqno: read_u32(payload, pos)?,
+			hops: read_u8(payload, pos)?,
+			cost: read_u32(payload, pos)?,
+		})
+	}
+}
+
+#[derive(Clone)]
+struct Rerr {
+	dest: ID,
+	dest_seqno: u32,
+}
+
+impl Rerr {
+	fn write(&self, payload: &mut Vec<u8>) {
+		write_u8(payload, RERR);
+		write_u32(payload, self.dest);
+		write_u32(payload, self.dest_seqno);
+	}
+
+	fn read(payload: &[u8], pos: &mut usize) -> Option<Self> {
+		Some(Self {
+			dest: read_u32(payload, pos)?,
+			dest_seqno: read_u32(payload, pos)?,
+		})
+	}
+}
+
+#[derive(Clone)]
+struct Route {
+	next_hop: ID,
+	hops: u8,
+	cost: u32,
+	seqno: u32,
+	valid: bool,
+	expires: u32,
+}
+
+// route discovery in progress
+#[derive(Clone)]
+struct Discovery {
+	retries: u32,
+	next_try: u32,
+}
+
+// packet to send after the current step
+enum Outgoing {
+	Unicast(ID, Vec<u8>),
+	Broadcast(Vec<u8>),
+}
+
+#[derive(Clone)]
+struct Node {
+	id: ID,
+	seqno: u32,
+	rreq_id: u32,
+	routes: BTreeMap<ID, Route>,
+	// (origin, rreq_id) => (time seen, lowest cost seen)
+	seen: BTreeMap<(ID, u32), (u32, u32)>,
+	discoveries: BTreeMap<ID, Discovery>,
+	// route errors to send
+	errors: Vec<Rerr>,
+}
+
+impl Node {
+	fn new(id: ID) -> Self {
+		Self {
+			id,
+			seqno: 1,
+			rreq_id: 0,
+			routes: BTreeMap::new(),
+			seen: BTreeMap::new(),
+			discoveries: BTreeMap::new(),
+			errors: vec![],
+		}
+	}
+
+	fn get_route(&self, dest: ID, time: u32) -> Option<&Route> {
+		self.routes.get(&dest).filter(|route| route.valid && route.expires >= time)
+	}
+
+	// add or update a route if it is fresher or cheaper
+	fn update_route(&mut self, dest: ID, next_hop: ID, hops: u8, cost: u32, seqno: u32, expires: u32) {
+		if dest == self.id {
+			return;
+		}
+
+		let route = self.routes.entry(dest).or_insert(Route {
+			next_hop,
+			hops,
+			cost,
+			seqno,
+			valid: false,
+			expires: 0,
+		});
+
+		let is_better = !route.valid
+			|| seqno > route.seqno
+			|| (seqno == route.seqno && cost < route.cost);
+
+		if is_better {
+			route.next_hop = next_hop;
+			route.hops = hops;
+			route.cost = cost;
+			route.seqno = seqno;
+			route.valid = true;
+		}
+
+		if route.next_hop == next_hop && expires > route.expires {
+			route.expires = expires;
+		}
+
+		// the discovery has finished
+		self.discoveries.remove(&dest);
+	}
+
+	// invalidate all routes over a broken link
+	fn link_broken(&mut self, next_hop: ID) {
+		for (dest, route) in self.routes.iter_mut() {
+			if route.valid && route.next_hop == next_hop {
+				route.valid = false;
+				route.seqno += 1;
+				self.errors.push(Rerr { dest: *dest, dest_seqno: route.seqno });
+			}
+		}
+	}
+
+	fn create_rreq(&mut self, dest: ID) -> Rreq {
+		self.seqno += 1;
+		self.rreq_id += 1;
+		self.seen.insert((self.id, self.rreq_id), (0, 0));
+
+		Rreq {
+			rreq_id: self.rreq_id,
+			origin: self.id,
+			origin_seqno: self.seqno,
+			dest,
+			dest_seqno: self.routes.get(&dest).map_or(0, |route| route.seqno),
+			hops: 0,
+			cost: 0,
+			ttl: MAX_TTL,
+		}
+	}
+
+	fn handle_rreq(&mut self, from: ID, link_cost: u32, rreq: Rreq, time: u32, timeout: u32, out: &mut Vec<Outgoing>) {
+		let hops = rreq.hops.saturating_add(1);
+		let cost = rreq.cost.saturating_add(link_cost);
+
+		// only process copies of a request that took a cheaper path
+		let key = (rreq.origin, rreq.rreq_id);
+		if rreq.origin == self.id || self.seen.get(&key).is_some_and(|seen| cost >= seen.1) {
+			return;
+		}
+		self.seen.insert(key, (time, cost));
+
+		// route to the neighbor and reverse route to the originator
+		let neighbor_seqno = self.routes.get(&from).map_or(0, |route| route.seqno);
+		self.update_route(from, from, 1, link_cost, neighbor_seqno, time + timeout);
+		self.update_route(rreq.origin, from, hops, cost, rreq.origin_seqno, time + timeout);
+
+		if rreq.dest == self.id {
+			// destination reply
+			if rreq.dest_seqno > self.seqno {
+				self.seqno = rreq.dest_seqno;
+			}
+			let mut payload = Vec::new();
+			Rrep { origin: rreq.origin, dest: self.id, dest_seqno: self.seqno, hops: 0, cost: 0 }.write(&mut payload);
+			out.push(Outgoing::Unicast(from, payload));
+		} else if let Some(route) = self.get_route(rreq.dest, time).filter(|route| rreq.dest_seqno != 0 && route.seqno >= rreq.dest_seqno) {
+			// intermediate reply from a fresh enough route
+			let mut payload = Vec::new();
+			Rrep { origin: rreq.origin, dest: rreq.dest, dest_seqno: route.seqno, hops: route.hops, cost: route.cost }.write(&mut payload);
+			out.push(Outgoing::Unicast(from, payload));
+		} else if rreq.ttl > 1 {
+			let mut payload = Vec::new();
+			Rreq { hops, cost, ttl: rreq.ttl - 1, ..rreq }.write(&mut payload);
+			out.push(Outgoing::Broadcast(payload));
+		}
+	}
+
+	fn handle_rrep(&mut self, from: ID, link_cost: u32, rrep: Rrep, time: u32, timeout: u32, out: &mut Vec<Outgoing>) {
+		let hops = rrep.hops.saturating_add(1);
+		let cost = rrep.cost.saturating_add(link_cost);
+
+		self.update_route(rrep.dest, from, hops, cost, rrep.dest_seqno, time + timeout);
+
+		if rrep.origin == self.id {
+			return;
+		}
+
+		// forward along the reverse route
+		if let Some(route) = self.get_route(rrep.origin, time) {
+			let mut payload = Vec::new();
+			Rrep { hops, cost, ..rrep }.write(&mut payload);
+			out.push(Outgoing::Unicast(route.next_hop, payload));
+		}
+	}
+
+	fn handle_rerr(&mut self, from: ID, rerr: Rerr, out: &mut Vec<Outgoing>) {
+		if let Some(route) = self.routes.get_mut(&rerr.dest) {
+			if route.valid && route.next_hop == from {
+				route.valid = false;
+				if rerr.dest_seqno > route.seqno {
+					route.seqno = rerr.dest_seqno;
+				}
+
+				// inform upstream nodes
+				let mut payload = Vec::new();
+				Rerr { dest: rerr.dest, dest_seqno: route.seqno }.write(&mut payload);
+				out.push(Outgoing::Broadcast(payload));
+			}
+		}
+	}
+}
+
+pub struct AodvRouting {
+	nodes: Vec<Node>,
+	time: u32,
+	route_timeout: u32,
+	rreq_retries: u32,
+	rreq_wait: u32,
+}
+
+impl AodvRouting {
+	pub fn new() -> Self {
+		Self {
+			nodes: vec![],
+			time: 0,
+			route_timeout: 30,
+			rreq_retries: 2,
+			rreq_wait: 20,
+		}
+	}
+
+	fn handle_message(&mut self, id: usize, message: &Message, link_cost: u32, out: &mut Vec<Outgoing>) {
+		let time = self.time;
+		let timeout = self.route_timeout;
+		let node = &mut self.nodes[id];
+		let payload = &message.payload;
+		let mut pos = 0;
+
+		while let Some(msg) = read_u8(payload, &mut pos) {
+			match msg {
+				RREQ => if let Some(rreq) = Rreq::read(payload, &mut pos) {
+					node.handle_rreq(message.from, link_cost, rreq, time, timeout, out);
+					continue;
+				},
+				RREP => if let Some(rrep) = Rrep::read(payload, &mut pos) {
+					node.handle_rrep(message.from, link_cost, rrep, time, timeout, out);
+					continue;
+				},
+				RERR => if let Some(rerr) = Rerr::read(payload, &mut pos) {
+					node.handle_rerr(message.from, rerr, out);
+					continue;
+				},
+				_ => {}
+			}
+			break;
+		}
+	}
+
+	fn send(id: ID, out: Vec<Outgoing>, io: &mut Io) {
+		for outgoing in out {
+			match outgoing {
+				Outgoing::Unicast(to, payload) => {
+					io.send(id, to, payload);
+				},
+				Outgoing::Broadcast(payload) => {
+					io.broadcast(id, payload);
+				}
+			}
+		}
+	}
+}
+
+impl RoutingAlgorithm for AodvRouting
+{
+	fn get_node(&self, id: ID, key: &str, out: &mut dyn std::fmt::Write) -> Result<(), std::fmt::Error> {
+		let node = &self.nodes[id as usize];
+		match key {
+			"name" => {
+				let routes = node.routes.keys().filter(|dest| node.get_route(**dest, self.time).is_some()).count();
+				write!(out, "{} ({})", id, routes)?;
+			},
+			"label" => {
+				write!(out, "{}", node.seqno)?;
+			},
+			_ => {}
+		}
+		Ok(())
+	}
+
+	fn get(&self, key: &str, out: &mut dyn std::fmt::Write) -> Result<(), std::fmt::Error> {
+		match key {
+			"name" => {
+				write!(out, "AODV Routing")?;
+			},
+			"description" => {
+				write!(out, "{}", concat!(
+					"Discover routes on demand by flooding route requests. ",
+					"Route replies travel back along the reverse route."
+				))?;
+			},
+			"route_timeout" => {
+				write!(out, "{}", self.route_timeout)?;
+			},
+			"rreq_retries" => {
+				write!(out, "{}", self.rreq_retries)?;
+			},
+			"rreq_wait" => {
+				write!(out, "{}", self.rreq_wait)?;
+			},
+			_ => {}
+		}
+		Ok(())
+	}
+
+	fn set(&mut self, key: &str, value: &str) -> Result<(), std::fmt::Error> {
+		match key {
+			"route_timeout" => {
+				match value.parse::<u32>() {
+					Ok(timeout) if timeout > 0 => {
+						self.route_timeout = timeout;
+					},
+					_ => {
+						println!("invalid route_timeout value");
+					}
+				}
+			},
+			"rreq_retries" => {
+				match value.parse::<u32>() {
+					Ok(retries) => {
+						self.rreq_retries = retries;
+					},
+					_ => {
+						println!("invalid rreq_retries value");
+					}
+				}
+			},
+			"rreq_wait" => {
+				match value.parse::<u32>() {
+					Ok(wait) if wait > 0 => {
+						self.rreq_wait = wait;
+					},
+					_ => {
+						println!("invalid rreq_wait value");
+					}
+				}
+			},
+			_ => {}
+		}
+		Ok(())
+	}
+
+	fn reset(&mut self, len: usize, _rng: &mut StdRng) {
+		self.nodes = (0..len).map(|id| Node::new(id as ID)).collect();
+		self.time = 0;
+	}
+
+	fn step(&mut self, io: &mut Io) {
+		self.time += 1;
+
+		let time = self.time;
+		let seen_timeout = 2 * self.route_timeout;
+
+		for id in 0..self.nodes.len() {
+			let mut out = Vec::new();
+
+			self.nodes[id].seen.retain(|_, seen| (seen.0 + seen_timeout) >= time);
+
+			for message in io.receive(id as ID) {
+				let link_cost = io.link_cost(id as ID, message.from).unwrap_or(1) as u32;
+				self.handle_message(id, &message, link_cost, &mut out);
+			}
+
+			let errors = std::mem::take(&mut self.nodes[id].errors);
+			if !errors.is_empty() {
+				let mut payload = Vec::new();
+				for rerr in errors {
+					rerr.write(&mut payload);
+				}
+				out.push(Outgoing::Broadcast(payload));
+			}
+
+			Self::send(id as ID, out, io);
+		}
+	}
+
+	fn link_removed(&mut self, from: ID, to: ID) {
+		if let Some(node) = self.nodes.get_mut(from as usize) {
+			node.link_broken(to);
+		}
+	}
+
+	fn is_reactive(&self) -> bool {
+		true
+	}
+
+	fn forward(&mut self, packet: &TestPacket, io: &mut Io) -> Forward {
+		let time = self.time;
+		let timeout = self.route_timeout;
+		let id = packet.receiver;
+		let dest = packet.destination;
+		let node = &mut self.nodes[id as usize];
+		let mut out = Vec::new();
+
+		if let Some(next_hop) = node.get_route(dest, time).map(|route| route.next_hop) {
+			if io.link_quality(id, next_hop).is_some() {
+				// active routes stay alive
+				node.routes.get_mut(&dest).unwrap().expires = time + timeout;
+				return Forward::Next(next_hop);
+			}
+
+			// link break => invalidate route and inform upstream nodes
+			let route = node.routes.get_mut(&dest).unwrap();
+			route.valid = false;
+			route.seqno += 1;
+			let mut payload = Vec::new();
+			Rerr { dest, dest_seqno: route.seqno }.write(&mut payload);
+			out.push(Outgoing::Broadcast(payload));
+		}
+
+		let ret = match node.discoveries.get(&dest).cloned() {
+			Some(discovery) if time < discovery.next_try => {
+				Forward::Buffered
+			},
+			Some(discovery) if discovery.retries >= self.rreq_retries => {
+				// give up
+				node.discoveries.remove(&dest);
+				Forward::Drop
+			},
+			discovery => {
+				// (re)start route discovery, double wait time on every retry
+				let retries = discovery.map_or(0, |d| d.retries + 1);
+				node.discoveries.insert(dest, Discovery {
+					retries,
+					next_try: time.saturating_add(self.rreq_wait.saturating_mul(2u32.saturating_pow(retries))),
+				});
+				let mut payload = Vec::new();
+				node.create_rreq(dest).write(&mut payload);
+				out.push(Outgoing::Broadcast(payload));
+				Forward::Buffered
+			}
+		};
+
+		Self::send(id, out, io);
+
+		ret
+	}
+
+	fn route(&self, packet: &TestPacket) -> Option<ID> {
+		self.nodes[packet.receiver as usize].get_route(packet.destination, self.time).map(|route| route.next_hop)
+	}
+}
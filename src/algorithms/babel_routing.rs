@@ -0,0 +1,472 @@
+use std::collections::BTreeMap;
+
+use rand::rngs::StdRng;
+use crate::utils::*;
+use crate::graph::ID;
+use crate::link_queues::Message;
+use crate::sim::{Io, RoutingAlgorithm, TestPacket};
+
+/*
+* Babel like distance vector routing (RFC 8966).
+* Routes carry sequence numbers and only feasible routes are selected
+* to avoid routing loops. Lost routes trigger updates and seqno requests.
+*/
+
+const INFINITY : u16 = u16::MAX;
+
+// number of hello intervals used to estimate the link cost
+const HELLO_WINDOW : u32 = 16;
+
+const TLV_HELLO : u8 = 1;
+const TLV_UPDATE : u8 = 2;
+const TLV_SEQNO_REQUEST : u8 = 3;
+
+// seqno a is newer than b (modulo 2^16)
+fn seqno_gt(a: u16, b: u16) -> bool {
+	a != b && (a.wrapping_sub(b) as i16) > 0
+}
+
+fn add_metric(a: u16, b: u16) -> u16 {
+	std::cmp::min(a as u32 + b as u32, INFINITY as u32) as u16
+}
+
+#[derive(Clone)]
+struct Neighbor {
+	id: ID,
+	// bit set for every hello interval a hello was received
+	history: u32,
+	// number of intervals since the neighbor appeared
+	age: u32,
+}
+
+impl Neighbor {
+	fn new(id: ID) -> Self {
+		Self { id, history: 0, age: 0 }
+	}
+
+	// link cost of the selected metric, increased by hello loss (like ETX)
+	fn cost(&self, link_cost: u16) -> u16 {
+		let received = self.history.count_ones();
+		let window = std::cmp::min(self.age + 1, HELLO_WINDOW);
+		if received == 0 {
+			INFINITY
+		} else {
+			let cost = (link_cost as u32 * window).div_ceil(received);
+			std::cmp::min(cost, INFINITY as u32 - 1) as u16
+		}
+	}
+}
+
+// route as announced by a neighbor
+#[derive(Clone)]
+struct Route {
+	seqno: u16,
+	// metric announced by the neighbor
+	metric: u16,
+	last_updated: u32,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+struct Selected {
+	next_hop: ID,
+	seqno: u16,
+	metric: u16,
+}
+
+// feasibility distance
+#[derive(Clone, Copy)]
+struct Source {
+	seqno: u16,
+	metric: u16,
+}
+
+struct Request {
+	to: ID,
+	destination: ID,
+	seqno: u16,
+	hops: u8,
+}
+
+#[derive(Clone)]
+struct Node {
+	id: ID,
+	seqno: u16,
+	neighbors: Vec<Neighbor>,
+	// (destination, neighbor) => route
+	routes: BTreeMap<(ID, ID), Route>,
+	selected: BTreeMap<ID, Selected>,
+	sources: BTreeMap<ID, Source>,
+	// destinations that need a triggered update
+	triggered: Vec<ID>,
+	// destinations that lost their route
+	retracted: Vec<(ID, u16)>,
+	// last time a seqno request was send for a destination
+	requested: BTreeMap<ID, u32>,
+}
+
+impl Node {
+	fn new(id: ID) -> Self {
+		Self {
+			id,
+			seqno: 0,
+			neighbors: vec![],
+			routes: BTreeMap::new(),
+			selected: BTreeMap::new(),
+			sources: BTreeMap::new(),
+			triggered: vec![],
+			retracted: vec![],
+			requested: BTreeMap::new(),
+		}
+	}
+
+	fn get_neighbor(&self, id: ID) -> Option<&Neighbor> {
+		self.neighbors.iter().find(|n| n.id == id)
+	}
+
+	// update history of all neighbors and remove silent ones
+	fn age_neighbors(&mut self) {
+		for neighbor in &mut self.neighbors {
+			neighbor.history = (neighbor.history << 1) & ((1 << HELLO_WINDOW) - 1);
+			neighbor.age += 1;
+		}
+
+		// no hello in the last four intervals
+		let routes = &mut self.routes;
+		self.neighbors.retain(|n| {
+			if (n.history & 0x1E) == 0 && n.age > 4 {
+				routes.retain(|&(_, via), _| via != n.id);
+				false
+			} else {
+				true
+			}
+		});
+	}
+
+	fn is_feasible(&self, destination: ID, seqno: u16, metric: u16) -> bool {
+		if metric == INFINITY {
+			return false;
+		}
+
+		if let Some(source) = self.sources.get(&destination) {
+			seqno_gt(seqno, source.seqno) || (seqno == source.seqno && metric < source.metric)
+		} else {
+			true
+		}
+	}
+
+	// remember the smallest metric we announced
+	fn update_source(&mut self, destination: ID, seqno: u16, metric: u16) {
+		if metric == INFINITY {
+			return;
+		}
+
+		let source = self.sources.entry(destination).or_insert(Source { seqno, metric });
+		if seqno_gt(seqno, source.seqno) {
+			*source = Source { seqno, metric };
+		} else if seqno == source.seqno && metric < source.metric {
+			source.metric = metric;
+		}
+	}
+
+	fn handle_hello(&mut self, from: ID) {
+		if let Some(neighbor) = self.neighbors.iter_mut().find(|n| n.id == from) {
+			neighbor.history |= 1;
+		} else {
+			let mut neighbor = Neighbor::new(from);
+			neighbor.history = 1;
+			self.neighbors.push(neighbor);
+		}
+	}
+
+	fn handle_update(&mut self, from: ID, destination: ID, seqno: u16, metric: u16, time: u32) {
+		if destination == self.id {
+			return;
+		}
+
+		if metric == INFINITY {
+			// retraction
+			self.routes.remove(&(destination, from));
+		} else {
+			self.routes.insert((destination, from), Route { seqno, metric, last_updated: time });
+		}
+	}
+
+	fn handle_request(&mut self, from: ID, destination: ID, seqno: u16, hops: u8, requests: &mut Vec<Request>) {
+		if destination == self.id {
+			if seqno_gt(seqno, self.seqno) {
+				self.seqno = seqno;
+			}
+			self.triggered.push(destination);
+		} else if let Some(selected) = self.selected.get(&destination) {
+			if !seqno_gt(seqno, selected.seqno) {
+				// our route is recent enough
+				self.triggered.push(destination);
+			} else if hops > 1 && selected.next_hop != from {
+				// forward towards the destination
+				requests.push(Request {
+					to: selected.next_hop,
+					destination,
+					seqno,
+					hops: hops - 1
+				});
+			}
+		}
+	}
+
+	fn expire_routes(&mut self, time: u32, timeout: u32) {
+		self.routes.retain(|_, route| (route.last_updated + timeout) >= time);
+	}
+
+	// select the best feasible route for every destination
+	fn select_routes(&mut self, io: &Io, time: u32, update_interval: u32, requests: &mut Vec<Request>) {
+		let mut selected = BTreeMap::<ID, Selected>::new();
+		let mut starving = BTreeMap::<ID, (ID, u16)>::new();
+
+		for (&(destination, via), route) in &self.routes {
+			let cost = if let Some(neighbor) = self.get_neighbor(via) {
+				neighbor.cost(io.link_cost(self.id, via).unwrap_or(1))
+			} else {
+				continue;
+			};
+
+			let metric = add_metric(route.metric, cost);
+			if metric == INFINITY {
+				continue;
+			}
+
+			if !self.is_feasible(destination, route.seqno, route.metric) {
+				// keep the neighbor in mind to ask for a new seqno
+				starving.insert(destination, (via, route.seqno));
+				continue;
+			}
+
+			let better = if let Some(current) = selected.get(&destination) {
+				metric < current.metric
+			} else {
+				true
+			};
+
+			if better {
+				selected.insert(destination, Selected { next_hop: via, seqno: route.seqno, metric });
+			}
+		}
+
+		// trigger updates on significant changes
+		for (destination, new) in &selected {
+			match self.selected.get(destination) {
+				Some(old) if old.next_hop == new.next_hop && old.seqno == new.seqno => {},
+				_ => self.triggered.push(*destination),
+			}
+		}
+
+		for (destination, old) in &self.selected {
+			if !selected.contains_key(destination) {
+				self.retracted.push((*destination, old.seqno));
+			}
+		}
+
+		// no feasible route left => ask for a newer seqno
+		for (destination, (via, seqno)) in starving {
+			if selected.contains_key(&destination) {
+				continue;
+			}
+
+			let last = *self.requested.get(&destination).unwrap_or(&0);
+			if last == 0 || (last + update_interval) <= time {
+				let seqno = if let Some(source) = self.sources.get(&destination) {
+					source.seqno.wrapping_add(1)
+				} else {
+					seqno.wrapping_add(1)
+				};
+				requests.push(Request { to: via, destination, seqno, hops: 16 });
+				self.requested.insert(destination, time);
+			}
+		}
+
+		self.selected = selected;
+	}
+
+	fn write_update(&mut self, payload: &mut Vec<u8>, destination: ID, seqno: u16, metric: u16) {
+		write_u8(payload, TLV_UPDATE);
+		write_u32(payload, destination);
+		write_u16(payload, seqno);
+		write_u16(payload, metric);
+		self.update_source(destination, seqno, metric);
+	}
+
+	fn create_packet(&mut self, full_update: bool) -> Vec<u8> {
+		let mut payload = Vec::new();
+		write_u8(&mut payload, TLV_HELLO);
+
+		let mut destinations = std::mem::take(&mut self.triggered);
+		if full_update {
+			destinations.push(self.id);
+			destinations.extend(self.selected.keys());
+		}
+		destinations.sort();
+		destinations.dedup();
+
+		for destination in destinations {
+			if destination == self.id {
+				let seqno = self.seqno;
+				self.write_update(&mut payload, destination, seqno, 0);
+			} else if let Some(selected) = self.selected.get(&destination).cloned() {
+				self.write_update(&mut payload, destination, selected.seqno, selected.metric);
+			}
+		}
+
+		for (destination, seqno) in std::mem::take(&mut self.retracted) {
+			self.write_update(&mut payload, destination, seqno, INFINITY);
+		}
+
+		payload
+	}
+
+	fn route(&self, packet: &TestPacket) -> Option<ID> {
+		self.selected.get(&packet.destination).map(|selected| selected.next_hop)
+	}
+}
+
+pub struct BabelRouting {
+	nodes: Vec<Node>,
+	time: u32,
+	update_interval: u32,
+}
+
+impl BabelRouting {
+	pub fn new() -> Self {
+		Self {
+			nodes: vec![],
+			time: 0,
+			update_interval: 4,
+		}
+	}
+
+	fn handle_message(&mut self, id: usize, message: &Message, requests: &mut Vec<Request>) {
+		let node = &mut self.nodes[id];
+		let payload = &message.payload;
+		let mut pos = 0;
+
+		while let Some(tlv) = read_u8(payload, &mut pos) {
+			match tlv {
+				TLV_HELLO => {
+					node.handle_hello(message.from);
+				},
+				TLV_UPDATE => {
+					if let (Some(destination), Some(seqno), Some(metric)) =
+						(read_u32(payload, &mut pos), read_u16(payload, &mut pos), read_u16(payload, &mut pos)) {
+						node.handle_update(message.from, destination, seqno, metric, self.time);
+					} else {
+						break;
+					}
+				},
+				TLV_SEQNO_REQUEST => {
+					if let (Some(destination), Some(seqno), Some(hops)) =
+						(read_u32(payload, &mut pos), read_u16(payload, &mut pos), read_u8(payload, &mut pos)) {
+						node.handle_request(message.from, destination, seqno, hops, requests);
+					} else {
+						break;
+					}
+				},
+				_ => {
+					// unknown tlv
+					break;
+				}
+			}
+		}
+	}
+}
+
+impl RoutingAlgorithm for BabelRouting
+{
+	fn get_node(&self, id: ID, key: &str, out: &mut dyn std::fmt::Write) -> Result<(), std::fmt::Error> {
+		let node = &self.nodes[id as usize];
+		match key {
+			"name" => {
+				write!(out, "{} ({})", id, node.selected.len())?;
+			},
+			"label" => {
+				write!(out, "{}", node.seqno)?;
+			},
+			_ => {}
+		}
+		Ok(())
+	}
+
+	fn get(&self, key: &str, out: &mut dyn std::fmt::Write) -> Result<(), std::fmt::Error> {
+		match key {
+			"name" => {
+				write!(out, "Babel Routing")?;
+			},
+			"description" => {
+				write!(out, "{}", concat!(
+					"Distance vector routing with sequence numbers and ",
+					"a feasibility condition to avoid routing loops."
+				))?;
+			},
+			"update_interval" => {
+				write!(out, "{}", self.update_interval)?;
+			},
+			_ => {}
+		}
+		Ok(())
+	}
+
+	fn set(&mut self, key: &str, value: &str) -> Result<(), std::fmt::Error> {
+		if key == "update_interval" {
+			match value.parse::<u32>() {
+				Ok(interval) if interval > 0 => {
+					self.update_interval = interval;
+				},
+				_ => {
+					println!("invalid update_interval value");
+				}
+			}
+		}
+		Ok(())
+	}
+
+	fn reset(&mut self, len: usize, _rng: &mut StdRng) {
+		self.nodes = (0..len).map(|id| Node::new(id as ID)).collect();
+		self.time = 0;
+	}
+
+	fn step(&mut self, io: &mut Io) {
+		self.time += 1;
+
+		let time = self.time;
+		let update_interval = self.update_interval;
+		let route_timeout = (7 * update_interval) / 2;
+		let mut requests = Vec::new();
+
+		for id in 0..self.nodes.len() {
+			self.nodes[id].age_neighbors();
+
+			for message in io.receive(id as ID) {
+				self.handle_message(id, &message, &mut requests);
+			}
+
+			let node = &mut self.nodes[id];
+			node.expire_routes(time, route_timeout);
+			node.select_routes(io, time, update_interval, &mut requests);
+
+			// periodic updates are spread over all steps
+			let full_update = (time + id as u32).is_multiple_of(update_interval);
+			let payload = node.create_packet(full_update);
+			io.broadcast(id as ID, payload);
+
+			for request in requests.drain(..) {
+				let mut payload = Vec::new();
+				write_u8(&mut payload, TLV_SEQNO_REQUEST);
+				write_u32(&mut payload, request.destination);
+				write_u16(&mut payload, request.seqno);
+				write_u8(&mut payload, request.hops);
+				io.send(id as ID, request.to, payload);
+			}
+		}
+	}
+
+	fn route(&self, packet: &TestPacket) -> Option<ID> {
+		self.nodes[packet.receiver as usize].route(packet)
+	}
+}
@@ -0,0 +1,353 @@
+use std::collections::BTreeMap;
+
+use rand::rngs::StdRng;
+use crate::utils::*;
+use crate::graph::ID;
+use crate::link_queues::Message;
+use crate::sim::{Io, RoutingAlgorithm, TestPacket};
+
+/*
+* BATMAN IV like routing.
+* Every node floods originator messages (OGM) with a transmit quality (TQ).
+* The TQ is reduced on every hop by the link quality and a hop penalty.
+* Each node forwards packets to the neighbor with the best TQ towards the originator.
+* The selected metric is not used, routes only depend on the link quality.
+*/
+
+const TQ_MAX : u32 = 255;
+
+// number of recent sequence numbers considered for ranking
+const WINDOW_SIZE : u32 = 16;
+
+// number of TQ values used for the average
+const TQ_RING_SIZE : usize = 5;
+
+const OGM : u8 = 1;
+
+#[derive(Clone)]
+struct Ogm {
+	originator: ID,
+	seqno: u32,
+	tq: u8,
+	ttl: u8,
+}
+
+impl Ogm {
+	fn write(&self, payload: &mut Vec<u8>) {
+		write_u8(payload, OGM);
+		write_u32(payload, self.originator);
+		write_u32(payload, self.seqno);
+		write_u8(payload, self.tq);
+		write_u8(payload, self.ttl);
+	}
+
+	fn read(payload: &[u8], pos: &mut usize) -> Option<Self> {
+		Some(Self {
+			originator: read_u32(payload, pos)?,
+			seqno: read_u32(payload, pos)?,
+			tq: read_u8(payload, pos)?,
+			ttl: read_u8(payload, pos)?,
+		})
+	}
+}
+
+// ranking of a neighbor as next hop towards an originator
+#[derive(Clone)]
+struct NeighborRank {
+	// (seqno, tq) of recent OGMs
+	tqs: Vec<(u32, u8)>,
+	last_seen: u32,
+}
+
+impl NeighborRank {
+	fn new() -> Self {
+		Self { tqs: Vec::with_capacity(TQ_RING_SIZE), last_seen: 0 }
+	}
+
+	fn add(&mut self, seqno: u32, tq: u8, time: u32) {
+		if self.tqs.len() == TQ_RING_SIZE {
+			self.tqs.remove(0);
+		}
+		self.tqs.push((seqno, tq));
+		self.last_seen = time;
+	}
+
+	// average TQ of OGMs inside the seqno window
+	fn tq_avg(&self, last_seqno: u32) -> u32 {
+		let mut sum = 0;
+		let mut count = 0;
+		for &(seqno, tq) in &self.tqs {
+			if seqno + WINDOW_SIZE > last_seqno {
+				sum += tq as u32;
+				count += 1;
+			}
+		}
+		sum.checked_div(count).unwrap_or(0)
+	}
+}
+
+#[derive(Clone)]
+struct Originator {
+	last_seqno: u32,
+	last_forwarded: Option<u32>,
+	last_seen: u32,
+	neighbors: BTreeMap<ID, NeighborRank>,
+	router: Option<ID>,
+	tq: u32,
+}
+
+impl Originator {
+	fn new() -> Self {
+		Self {
+			last_seqno: 0,
+			last_forwarded: None,
+			last_seen: 0,
+			neighbors: BTreeMap::new(),
+			router: None,
+			tq: 0,
+		}
+	}
+
+	fn update_router(&mut self) {
+		let mut router = None;
+		let mut best = 0;
+
+		for (id, rank) in &self.neighbors {
+			let tq = rank.tq_avg(self.last_seqno);
+			if tq > best {
+				best = tq;
+				router = Some(*id);
+			}
+		}
+
+		self.router = router;
+		self.tq = best;
+	}
+}
+
+#[derive(Clone)]
+struct Node {
+	id: ID,
+	seqno: u32,
+	originators: BTreeMap<ID, Originator>,
+	forward: Vec<Ogm>,
+}
+
+impl Node {
+	fn new(id: ID) -> Self {
+		Self {
+			id,
+			seqno: 0,
+			originators: BTreeMap::new(),
+			forward: vec![],
+		}
+	}
+
+	fn purge(&mut self, time: u32, timeout: u32) {
+		for originator in self.originators.values_mut() {
+			let before = originator.neighbors.len();
+			originator.neighbors.retain(|_, rank| (rank.last_seen + timeout) >= time);
+			if before != originator.neighbors.len() {
+				originator.update_router();
+			}
+		}
+		self.originators.retain(|_, o| (o.last_seen + timeout) >= time);
+	}
+
+	// link_tq: quality of the link to the sending neighbor
+	fn handle_ogm(&mut self, from: ID, ogm: Ogm, link_tq: u32, hop_penalty: u32, time: u32) {
+		if ogm.originator == self.id {
+			return;
+		}
+
+		let originator = self.originators.entry(ogm.originator).or_insert_with(Originator::new);
+
+		// outdated OGM
+		if originator.last_seqno >= WINDOW_SIZE + ogm.seqno {
+			return;
+		}
+
+		// decay TQ by link quality and hop penalty
+		let tq = (ogm.tq as u32) * link_tq / TQ_MAX;
+		let tq = tq * (TQ_MAX - hop_penalty) / TQ_MAX;
+
+		if ogm.seqno > originator.last_seqno {
+			originator.last_seqno = ogm.seqno;
+		}
+		originator.last_seen = time;
+		originator.neighbors.entry(from).or_insert_with(NeighborRank::new).add(ogm.seqno, tq as u8, time);
+		originator.update_router();
+
+		// rebroadcast OGMs from the best next hop, once per sequence number
+		let is_router = originator.router == Some(from);
+		let is_new = originator.last_forwarded.is_none_or(|seqno| ogm.seqno > seqno);
+
+		if ogm.ttl > 1 && is_new && (is_router || ogm.originator == from) {
+			originator.last_forwarded = Some(ogm.seqno);
+			self.forward.push(Ogm {
+				originator: ogm.originator,
+				seqno: ogm.seqno,
+				tq: tq as u8,
+				ttl: ogm.ttl - 1,
+			});
+		}
+	}
+
+	fn create_ogm(&mut self) -> Ogm {
+		self.seqno += 1;
+		Ogm {
+			originator: self.id,
+			seqno: self.seqno,
+			tq: TQ_MAX as u8,
+			ttl: 50,
+		}
+	}
+
+	fn route(&self, packet: &TestPacket) -> Option<ID> {
+		self.originators.get(&packet.destination).and_then(|o| o.router)
+	}
+}
+
+pub struct BatmanRouting {
+	nodes: Vec<Node>,
+	time: u32,
+	ogm_interval: u32,
+	hop_penalty: u32,
+}
+
+impl BatmanRouting {
+	pub fn new() -> Self {
+		Self {
+			nodes: vec![],
+			time: 0,
+			ogm_interval: 1,
+			hop_penalty: 15,
+		}
+	}
+
+	fn handle_message(&mut self, id: usize, message: &Message, io: &Io) {
+		// local link TQ from the quality of both link directions
+		let quality = |from, to| io.link_quality(from, to).unwrap_or(0) as u32;
+		let link_tq = TQ_MAX * quality(message.from, message.to) / (u16::MAX as u32)
+			* quality(message.to, message.from) / (u16::MAX as u32);
+
+		let payload = &message.payload;
+		let mut pos = 0;
+
+		while let Some(msg) = read_u8(payload, &mut pos) {
+			match (msg, Ogm::read(payload, &mut pos)) {
+				(OGM, Some(ogm)) => {
+					self.nodes[id].handle_ogm(message.from, ogm, link_tq, self.hop_penalty, self.time);
+				},
+				_ => {
+					break;
+				}
+			}
+		}
+	}
+}
+
+impl RoutingAlgorithm for BatmanRouting
+{
+	fn get_node(&self, id: ID, key: &str, out: &mut dyn std::fmt::Write) -> Result<(), std::fmt::Error> {
+		let node = &self.nodes[id as usize];
+		match key {
+			"name" => {
+				write!(out, "{} ({})", id, node.originators.len())?;
+			},
+			"label" => {
+				write!(out, "{}", node.seqno)?;
+			},
+			_ => {}
+		}
+		Ok(())
+	}
+
+	fn get(&self, key: &str, out: &mut dyn std::fmt::Write) -> Result<(), std::fmt::Error> {
+		match key {
+			"name" => {
+				write!(out, "BATMAN Routing")?;
+			},
+			"description" => {
+				write!(out, "{}", concat!(
+					"Flood originator messages with a transmit quality that ",
+					"decays per hop. Forward to the neighbor with the best transmit quality."
+				))?;
+			},
+			"ogm_interval" => {
+				write!(out, "{}", self.ogm_interval)?;
+			},
+			"hop_penalty" => {
+				write!(out, "{}", self.hop_penalty)?;
+			},
+			_ => {}
+		}
+		Ok(())
+	}
+
+	fn set(&mut self, key: &str, value: &str) -> Result<(), std::fmt::Error> {
+		match key {
+			"ogm_interval" => {
+				match value.parse::<u32>() {
+					Ok(interval) if interval > 0 => {
+						self.ogm_interval = interval;
+					},
+					_ => {
+						println!("invalid ogm_interval value");
+					}
+				}
+			},
+			"hop_penalty" => {
+				match value.parse::<u32>() {
+					Ok(penalty) if penalty <= TQ_MAX => {
+						self.hop_penalty = penalty;
+					},
+					_ => {
+						println!("invalid hop_penalty value");
+					}
+				}
+			},
+			_ => {}
+		}
+		Ok(())
+	}
+
+	fn reset(&mut self, len: usize, _rng: &mut StdRng) {
+		self.nodes = (0..len).map(|id| Node::new(id as ID)).collect();
+		self.time = 0;
+	}
+
+	fn step(&mut self, io: &mut Io) {
+		self.time += 1;
+
+		let time = self.time;
+		let purge_timeout = 10 * self.ogm_interval;
+
+		for id in 0..self.nodes.len() {
+			self.nodes[id].purge(time, purge_timeout);
+
+			for message in io.receive(id as ID) {
+				self.handle_message(id, &message, io);
+			}
+
+			let node = &mut self.nodes[id];
+			let mut payload = Vec::new();
+
+			if (time + id as u32).is_multiple_of(self.ogm_interval) {
+				node.create_ogm().write(&mut payload);
+			}
+
+			for ogm in node.forward.drain(..) {
+				ogm.write(&mut payload);
+			}
+
+			if !payload.is_empty() {
+				io.broadcast(id as ID, payload);
+			}
+		}
+	}
+
+	fn route(&self, packet: &TestPacket) -> Option<ID> {
+		self.nodes[packet.receiver as usize].route(packet)
+	}
+}
@@ -1,4 +1,5 @@
 
+use rand::rngs::StdRng;
 use crate::utils::*;
 use crate::graph::*;
 use crate::sim::{Io, RoutingAlgorithm, TestPacket};
@@ -9,12 +10,12 @@ struct Packet {
 	from: ID,
 	to: ID,
 	origin: ID,
-	hops: u32
+	metric: u32
 }
 
 impl Packet {
-	fn new(from: ID, to: ID, origin: ID, hops: u32) -> Self {
-		Self { from, to, origin, hops }
+	fn new(from: ID, to: ID, origin: ID, metric: u32) -> Self {
+		Self { from, to, origin, metric }
 	}
 }
 
@@ -22,7 +23,7 @@ impl Packet {
 struct Entry {
 	id: ID, // destination id
 	next: ID, // next hop id
-	hops: u32,
+	metric: u32, // sum of link costs
 	last_updated: u32
 }
 
@@ -67,8 +68,10 @@ impl Node {
 		// update exiting entry
 		for neighbor in &mut self.entries {
 			if neighbor.id == packet.origin {
-				if packet.hops <= neighbor.hops {
+				// always accept updates from the current next hop
+				if packet.metric <= neighbor.metric || packet.from == neighbor.next {
 					neighbor.next = packet.from;
+					neighbor.metric = packet.metric;
 					neighbor.last_updated = time;
 				}
 				return;
@@ -78,7 +81,7 @@ impl Node {
 		// add new entry
 		self.entries.push(Entry {
 			id: packet.origin,
-			hops: packet.hops,
+			metric: packet.metric,
 			next: packet.from,
 			last_updated: time
 		});
@@ -122,7 +125,7 @@ impl RoutingAlgorithm for DistanceVectorRouting
 		Ok(())
 	}
 
-	fn reset(&mut self, len: usize) {
+	fn reset(&mut self, len: usize, _rng: &mut StdRng) {
 		self.nodes = vec![Node::new(); len];
 		self.time = 0;
 	}
@@ -135,27 +138,35 @@ impl RoutingAlgorithm for DistanceVectorRouting
 			node.timeout_entries(self.time);
 		}
 
-		// For each link...
-		let mut nodes = self.nodes.clone();
-		for (from, to) in io.link_iter() {
-			let src = &self.nodes[from as usize];
-			let dst = &mut nodes[to as usize];
-			// way faster approach, but mixes old and new states!!
-			//let (src, dst) = index_two_mut(&mut self.nodes, from as usize, to as usize);
-
-			// Send own info to neighbor
-			dst.update_table(&Packet::new(from, to, from, 1), self.time);
-
-			// Forward new information to neighbor
-			for entry in &src.entries {
-				if entry.last_updated + 1 == self.time {
-					dst.update_table(&Packet::new(from, to, entry.id, entry.hops + 1), self.time);
+		// Process received updates
+		for id in 0..self.nodes.len() {
+			for message in io.receive(id as ID) {
+				let mut pos = 0;
+				let payload = &message.payload;
+				// add cost of the link back to the sender
+				let cost = io.link_cost(message.to, message.from).unwrap_or(1) as u32;
+				while let (Some(origin), Some(metric)) = (read_u32(payload, &mut pos), read_u32(payload, &mut pos)) {
+					let packet = Packet::new(message.from, message.to, origin, metric.saturating_add(cost));
+					self.nodes[id].update_table(&packet, self.time);
 				}
 			}
 		}
 
-		// update nodes states
-		self.nodes = nodes;
+		// Send own info and new information to neighbors
+		for (id, node) in self.nodes.iter().enumerate() {
+			let mut payload = Vec::new();
+			write_u32(&mut payload, id as ID);
+			write_u32(&mut payload, 0);
+
+			for entry in &node.entries {
+				if entry.last_updated == self.time {
+					write_u32(&mut payload, entry.id);
+					write_u32(&mut payload, entry.metric);
+				}
+			}
+
+			io.broadcast(id as ID, payload);
+		}
 	}
 
 	fn route(&self, packet: &TestPacket) -> Option<ID> {
@@ -1,6 +1,7 @@
 use std::f32;
 use std::u32;
 
+use rand::rngs::StdRng;
 use crate::utils::*;
 use crate::graph::*;
 use crate::sim::{Io, RoutingAlgorithm, TestPacket};
@@ -256,10 +257,10 @@ impl RoutingAlgorithm for GeneticRouting
 		Ok(())
 	}
 
-	fn reset(&mut self, len: usize) {
+	fn reset(&mut self, len: usize, rng: &mut StdRng) {
 		self.nodes = vec![Node::new(); len];
 		for node in &mut self.nodes {
-			node.pos = Vec3::random_unit();
+			node.pos = Vec3::random_unit(rng);
 		}
 		self.time = 0;
 	}
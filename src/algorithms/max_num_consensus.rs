@@ -1,6 +1,8 @@
 
 use std::usize;
 
+use rand::Rng;
+use rand::rngs::StdRng;
 use crate::graph::ID;
 use crate::sim::{Io, RoutingAlgorithm};
 
@@ -59,12 +61,12 @@ impl RoutingAlgorithm for MaxNumConsensus
 		Ok(())
 	}
 
-	fn reset(&mut self, len: usize) {
+	fn reset(&mut self, len: usize, rng: &mut StdRng) {
 		self.nodes = vec![Node::new(); len];
 
 		// Assign random numbers
 		for i in 0..len {
-			self.nodes[i].num = rand::random::<u32>();
+			self.nodes[i].num = rng.gen::<u32>();
 		}
 	}
 
@@ -1,4 +1,3 @@
-
 pub mod vivaldi_routing;
 pub mod spring_routing;
 pub mod random_routing;
@@ -6,5 +5,97 @@ pub mod distance_vector_routing;
 pub mod max_num_consensus;
 pub mod distance_enumeration;
 pub mod spanning_tree_routing;
-//pub mod babel_routing;
+pub mod babel_routing;
+pub mod olsr_routing;
+pub mod batman_routing;
+pub mod aodv_routing;
 pub mod genetic_routing;
+
+use crate::sim::RoutingAlgorithm;
+use vivaldi_routing::VivaldiRouting;
+use spring_routing::SpringRouting;
+use random_routing::RandomRouting;
+use distance_vector_routing::DistanceVectorRouting;
+use max_num_consensus::MaxNumConsensus;
+use distance_enumeration::DistanceEnumeration;
+use spanning_tree_routing::SpanningTreeRouting;
+use genetic_routing::GeneticRouting;
+use babel_routing::BabelRouting;
+use olsr_routing::OlsrRouting;
+use batman_routing::BatmanRouting;
+use aodv_routing::AodvRouting;
+
+
+pub struct AlgorithmEntry {
+	pub name: &'static str,
+	pub description: &'static str,
+	pub new: fn() -> Box<dyn RoutingAlgorithm>,
+}
+
+// All selectable algorithms. New algorithms only need to be added here.
+pub const ALGORITHMS: &[AlgorithmEntry] = &[
+	AlgorithmEntry {
+		name: "random",
+		description: "Forward traffic to a random neighbor.",
+		new: || Box::new(RandomRouting::new()),
+	},
+	AlgorithmEntry {
+		name: "vivaldi",
+		description: "Greedy routing on Vivaldi coordinates.",
+		new: || Box::new(VivaldiRouting::new()),
+	},
+	AlgorithmEntry {
+		name: "spring",
+		description: "Greedy routing on virtual coordinates generated by spring forces.",
+		new: || Box::new(SpringRouting::new()),
+	},
+	AlgorithmEntry {
+		name: "genetic",
+		description: "Greedy routing on coordinates generated by a small program.",
+		new: || Box::new(GeneticRouting::new()),
+	},
+	AlgorithmEntry {
+		name: "tree",
+		description: "Routing along a spanning tree.",
+		new: || Box::new(SpanningTreeRouting::new()),
+	},
+	AlgorithmEntry {
+		name: "dv",
+		description: "Distance vector routing on hop counts.",
+		new: || Box::new(DistanceVectorRouting::new()),
+	},
+	AlgorithmEntry {
+		name: "babel",
+		description: "Babel like distance vector routing with feasibility condition.",
+		new: || Box::new(BabelRouting::new()),
+	},
+	AlgorithmEntry {
+		name: "olsr",
+		description: "OLSR like link state routing with Multi-Point Relays.",
+		new: || Box::new(OlsrRouting::new()),
+	},
+	AlgorithmEntry {
+		name: "batman",
+		description: "BATMAN IV like routing on originator messages and transmit quality.",
+		new: || Box::new(BatmanRouting::new()),
+	},
+	AlgorithmEntry {
+		name: "aodv",
+		description: "AODV like reactive routing with on demand route discovery.",
+		new: || Box::new(AodvRouting::new()),
+	},
+	AlgorithmEntry {
+		name: "max_num",
+		description: "Consensus on the maximum of random numbers (no routing).",
+		new: || Box::new(MaxNumConsensus::new()),
+	},
+	AlgorithmEntry {
+		name: "distance_enum",
+		description: "Distance to the node with the highest random number (no routing).",
+		new: || Box::new(DistanceEnumeration::new()),
+	},
+];
+
+pub fn find_algorithm(name: &str) -> Option<&'static AlgorithmEntry> {
+	ALGORITHMS.iter().find(|entry| entry.name == name)
+}
@@ -0,0 +1,518 @@
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BTreeSet, BinaryHeap};
+
+use rand::rngs::StdRng;
+use crate::utils::*;
+use crate::graph::ID;
+use crate::link_queues::Message;
+use crate::sim::{Io, RoutingAlgorithm, TestPacket};
+
+/*
+* OLSR like link state routing (RFC 3626).
+* HELLO messages detect symmetric neighbors and two hop neighbors.
+* Every node selects Multi-Point Relays (MPR) that cover all two hop neighbors.
+* Topology Control (TC) messages are only forwarded by MPRs.
+* HELLO and TC messages carry the link costs of the selected metric.
+*/
+
+const MSG_HELLO : u8 = 1;
+const MSG_TC : u8 = 2;
+
+const LINK_ASYM : u8 = 1;
+const LINK_SYM : u8 = 2;
+const LINK_MPR : u8 = 3;
+
+// ansn/seqno a is newer than b (modulo 2^16)
+fn seqno_gt(a: u16, b: u16) -> bool {
+	a != b && (a.wrapping_sub(b) as i16) > 0
+}
+
+#[derive(Clone)]
+struct Link {
+	symmetric: bool,
+	last_heard: u32,
+	// symmetric neighbors of this neighbor and the link costs to them
+	two_hop: Vec<(ID, u16)>,
+}
+
+#[derive(Clone)]
+struct Topology {
+	ansn: u16,
+	last_updated: u32,
+	// nodes that selected the originator as MPR and the link costs to them
+	selectors: Vec<(ID, u16)>,
+}
+
+#[derive(Clone)]
+struct Tc {
+	originator: ID,
+	ansn: u16,
+	seqno: u16,
+	ttl: u8,
+	selectors: Vec<(ID, u16)>,
+}
+
+impl Tc {
+	fn write(&self, payload: &mut Vec<u8>) {
+		write_u8(payload, MSG_TC);
+		write_u32(payload, self.originator);
+		write_u16(payload, self.ansn);
+		write_u16(payload, self.seqno);
+		write_u8(payload, self.ttl);
+		write_u16(payload, self.selectors.len() as u16);
+		for (id, cost) in &self.selectors {
+			write_u32(payload, *id);
+			write_u16(payload, *cost);
+		}
+	}
+
+	fn read(payload: &[u8], pos: &mut usize) -> Option<Self> {
+		let originator = read_u32(payload, pos)?;
+		let ansn = read_u16(payload, pos)?;
+		let seqno = read_u16(payload, pos)?;
+		let ttl = read_u8(payload, pos)?;
+		let count = read_u16(payload, pos)?;
+		let mut selectors = Vec::with_capacity(count as usize);
+		for _ in 0..count {
+			selectors.push((read_u32(payload, pos)?, read_u16(payload, pos)?));
+		}
+		Some(Self { originator, ansn, seqno, ttl, selectors })
+	}
+}
+
+#[derive(Clone)]
+struct Node {
+	id: ID,
+	links: BTreeMap<ID, Link>,
+	mprs: BTreeSet<ID>,
+	// neighbor => last time it selected us as MPR
+	mpr_selectors: BTreeMap<ID, u32>,
+	topology: BTreeMap<ID, Topology>,
+	// (originator, seqno) => time received
+	duplicates: BTreeMap<(ID, u16), u32>,
+	// destination => next hop
+	routes: BTreeMap<ID, ID>,
+	forward: Vec<Tc>,
+	ansn: u16,
+	seqno: u16,
+}
+
+impl Node {
+	fn new(id: ID) -> Self {
+		Self {
+			id,
+			links: BTreeMap::new(),
+			mprs: BTreeSet::new(),
+			mpr_selectors: BTreeMap::new(),
+			topology: BTreeMap::new(),
+			duplicates: BTreeMap::new(),
+			routes: BTreeMap::new(),
+			forward: vec![],
+			ansn: 0,
+			seqno: 0,
+		}
+	}
+
+	fn is_mpr(&self) -> bool {
+		!self.mpr_selectors.is_empty()
+	}
+
+	fn symmetric_neighbors(&self) -> Vec<ID> {
+		self.links.iter().filter(|(_, l)| l.symmetric).map(|(id, _)| *id).collect()
+	}
+
+	fn expire(&mut self, time: u32, neighbor_hold: u32, topology_hold: u32) {
+		let selectors_count = self.mpr_selectors.len();
+
+		self.links.retain(|_, l| (l.last_heard + neighbor_hold) >= time);
+		self.mpr_selectors.retain(|_, last| (*last + neighbor_hold) >= time);
+		self.topology.retain(|_, t| (t.last_updated + topology_hold) >= time);
+		self.duplicates.retain(|_, last| (*last + 3 * topology_hold) >= time);
+
+		if selectors_count != self.mpr_selectors.len() {
+			self.ansn = self.ansn.wrapping_add(1);
+		}
+	}
+
+	fn handle_hello(&mut self, from: ID, entries: &[(ID, u8, u16)], time: u32) {
+		let mut symmetric = false;
+		let mut selected = false;
+		let mut two_hop = Vec::new();
+
+		for &(id, status, cost) in entries {
+			if id == self.id {
+				symmetric = true;
+				selected = status == LINK_MPR;
+			} else if status == LINK_SYM || status == LINK_MPR {
+				two_hop.push((id, cost));
+			}
+		}
+
+		self.links.insert(from, Link { symmetric, last_heard: time, two_hop });
+
+		let was_selector = self.mpr_selectors.contains_key(&from);
+		if selected {
+			self.mpr_selectors.insert(from, time);
+		} else {
+			self.mpr_selectors.remove(&from);
+		}
+
+		if was_selector != selected {
+			self.ansn = self.ansn.wrapping_add(1);
+		}
+	}
+
+	fn handle_tc(&mut self, from: ID, tc: Tc, time: u32) {
+		if tc.originator == self.id || self.duplicates.contains_key(&(tc.originator, tc.seqno)) {
+			return;
+		}
+
+		self.duplicates.insert((tc.originator, tc.seqno), time);
+
+		// only accept links from a recent or the same advertised set
+		let accept = match self.topology.get(&tc.originator) {
+			Some(t) => !seqno_gt(t.ansn, tc.ansn),
+			None => true,
+		};
+
+		if accept {
+			self.topology.insert(tc.originator, Topology {
+				ansn: tc.ansn,
+				last_updated: time,
+				selectors: tc.selectors.clone(),
+			});
+		}
+
+		// only MPRs of the sender forward the message
+		if tc.ttl > 1 && self.mpr_selectors.contains_key(&from) {
+			let mut tc = tc;
+			tc.ttl -= 1;
+			self.forward.push(tc);
+		}
+	}
+
+	// Greedy MPR selection heuristic from RFC 3626
+	fn select_mprs(&mut self) {
+		let neighbors = self.symmetric_neighbors();
+
+		// strict two hop neighbors
+		let mut two_hop = BTreeSet::new();
+		for n in &neighbors {
+			for (id, _) in &self.links[n].two_hop {
+				if *id != self.id && !self.links.get(id).is_some_and(|l| l.symmetric) {
+					two_hop.insert(*id);
+				}
+			}
+		}
+
+		let covers = |n: &ID, id: &ID| self.links[n].two_hop.iter().any(|(t, _)| t == id);
+		let mut mprs = BTreeSet::new();
+
+		// neighbors that are the only way to reach a two hop neighbor
+		for id in &two_hop {
+			let reachable_by: Vec<&ID> = neighbors.iter().filter(|n| covers(n, id)).collect();
+			if reachable_by.len() == 1 {
+				mprs.insert(*reachable_by[0]);
+			}
+		}
+
+		let mut uncovered: BTreeSet<ID> = two_hop.iter()
+			.filter(|id| !mprs.iter().any(|n| covers(n, id)))
+			.cloned().collect();
+
+		// add neighbors that cover most of the remaining two hop neighbors
+		while !uncovered.is_empty() {
+			let mut best = None;
+			let mut best_count = 0;
+			for n in &neighbors {
+				if mprs.contains(n) {
+					continue;
+				}
+				let count = uncovered.iter().filter(|id| covers(n, id)).count();
+				if count > best_count {
+					best_count = count;
+					best = Some(*n);
+				}
+			}
+
+			if let Some(n) = best {
+				mprs.insert(n);
+				uncovered.retain(|id| !covers(&n, id));
+			} else {
+				break;
+			}
+		}
+
+		self.mprs = mprs;
+	}
+
+	// shortest paths (link costs) on the known topology
+	fn compute_routes(&mut self, io: &Io) {
+		// directed links with costs, the reverse direction is
+		// assumed to have the same costs unless it is known
+		let mut adjacency = BTreeMap::<ID, BTreeMap<ID, u32>>::new();
+		let mut connect = |a: ID, b: ID, cost: u16| {
+			adjacency.entry(a).or_default().insert(b, cost as u32);
+			adjacency.entry(b).or_default().entry(a).or_insert(cost as u32);
+		};
+
+		for (originator, t) in &self.topology {
+			for (selector, cost) in &t.selectors {
+				connect(*originator, *selector, *cost);
+			}
+		}
+
+		for (n, link) in &self.links {
+			if link.symmetric {
+				for (id, cost) in &link.two_hop {
+					connect(*n, *id, *cost);
+				}
+				connect(self.id, *n, io.link_cost(self.id, *n).unwrap_or(1));
+			}
+		}
+
+		// Dijkstra, remember first hop
+		let mut routes = BTreeMap::new();
+		let mut distances = BTreeMap::new();
+		let mut heap = BinaryHeap::new();
+
+		if let Some(neighbors) = adjacency.get(&self.id) {
+			for (n, cost) in neighbors {
+				heap.push(Reverse((*cost, *n, *n)));
+			}
+		}
+
+		while let Some(Reverse((distance, id, first_hop))) = heap.pop() {
+			if id == self.id || distances.contains_key(&id) {
+				continue;
+			}
+
+			distances.insert(id, distance);
+			routes.insert(id, first_hop);
+
+			if let Some(neighbors) = adjacency.get(&id) {
+				for (n, cost) in neighbors {
+					if !distances.contains_key(n) {
+						heap.push(Reverse((distance.saturating_add(*cost), *n, first_hop)));
+					}
+				}
+			}
+		}
+
+		self.routes = routes;
+	}
+
+	fn write_hello(&self, payload: &mut Vec<u8>, io: &Io) {
+		write_u8(payload, MSG_HELLO);
+		write_u16(payload, self.links.len() as u16);
+		for (id, link) in &self.links {
+			let status = if self.mprs.contains(id) {
+				LINK_MPR
+			} else if link.symmetric {
+				LINK_SYM
+			} else {
+				LINK_ASYM
+			};
+			write_u32(payload, *id);
+			write_u8(payload, status);
+			write_u16(payload, io.link_cost(self.id, *id).unwrap_or(1));
+		}
+	}
+
+	fn create_tc(&mut self, io: &Io) -> Tc {
+		self.seqno = self.seqno.wrapping_add(1);
+		let id = self.id;
+		Tc {
+			originator: id,
+			ansn: self.ansn,
+			seqno: self.seqno,
+			ttl: u8::MAX,
+			selectors: self.mpr_selectors.keys().map(|n| (*n, io.link_cost(id, *n).unwrap_or(1))).collect(),
+		}
+	}
+
+	fn route(&self, packet: &TestPacket) -> Option<ID> {
+		self.routes.get(&packet.destination).cloned()
+	}
+}
+
+pub struct OlsrRouting {
+	nodes: Vec<Node>,
+	time: u32,
+	hello_interval: u32,
+	tc_interval: u32,
+}
+
+impl OlsrRouting {
+	pub fn new() -> Self {
+		Self {
+			nodes: vec![],
+			time: 0,
+			hello_interval: 1,
+			tc_interval: 5,
+		}
+	}
+
+	fn handle_message(&mut self, id: usize, message: &Message) {
+		let node = &mut self.nodes[id];
+		let payload = &message.payload;
+		let mut pos = 0;
+
+		while let Some(msg) = read_u8(payload, &mut pos) {
+			match msg {
+				MSG_HELLO => {
+					let count = if let Some(count) = read_u16(payload, &mut pos) {
+						count
+					} else {
+						break;
+					};
+
+					let mut entries = Vec::with_capacity(count as usize);
+					for _ in 0..count {
+						if let (Some(id), Some(status), Some(cost)) = (read_u32(payload, &mut pos), read_u8(payload, &mut pos), read_u16(payload, &mut pos)) {
+							entries.push((id, status, cost));
+						}
+					}
+					node.handle_hello(message.from, &entries, self.time);
+				},
+				MSG_TC => {
+					if let Some(tc) = Tc::read(payload, &mut pos) {
+						node.handle_tc(message.from, tc, self.time);
+					} else {
+						break;
+					}
+				},
+				_ => {
+					break;
+				}
+			}
+		}
+	}
+
+	fn mprs_to_string(&self, id: ID) -> String {
+		let mprs: Vec<String> = self.nodes[id as usize].mprs.iter().map(|id| id.to_string()).collect();
+		mprs.join(",")
+	}
+}
+
+impl RoutingAlgorithm for OlsrRouting
+{
+	fn get_node(&self, id: ID, key: &str, out: &mut dyn std::fmt::Write) -> Result<(), std::fmt::Error> {
+		let node = &self.nodes[id as usize];
+		match key {
+			"name" => {
+				write!(out, "{} ({})", id, node.routes.len())?;
+			},
+			"label" => {
+				write!(out, "{}", node.mprs.len())?;
+			},
+			// mark nodes that were selected as MPR
+			"color" if node.is_mpr() => {
+				write!(out, "#ff0000")?;
+			},
+			"mprs" => {
+				write!(out, "{}", self.mprs_to_string(id))?;
+			},
+			"mpr_selectors" => {
+				let selectors: Vec<String> = node.mpr_selectors.keys().map(|id| id.to_string()).collect();
+				write!(out, "{}", selectors.join(","))?;
+			},
+			_ => {}
+		}
+		Ok(())
+	}
+
+	fn get(&self, key: &str, out: &mut dyn std::fmt::Write) -> Result<(), std::fmt::Error> {
+		match key {
+			"name" => {
+				write!(out, "OLSR Routing")?;
+			},
+			"description" => {
+				write!(out, "{}", concat!(
+					"Link state routing. Topology information is ",
+					"flooded via selected Multi-Point Relays."
+				))?;
+			},
+			"mpr_count" => {
+				let count = self.nodes.iter().filter(|n| n.is_mpr()).count();
+				write!(out, "{}", count)?;
+			},
+			"hello_interval" => {
+				write!(out, "{}", self.hello_interval)?;
+			},
+			"tc_interval" => {
+				write!(out, "{}", self.tc_interval)?;
+			},
+			_ => {}
+		}
+		Ok(())
+	}
+
+	fn set(&mut self, key: &str, value: &str) -> Result<(), std::fmt::Error> {
+		let interval = match value.parse::<u32>() {
+			Ok(interval) if interval > 0 => Some(interval),
+			_ => None,
+		};
+
+		match (key, interval) {
+			("hello_interval", Some(interval)) => {
+				self.hello_interval = interval;
+			},
+			("tc_interval", Some(interval)) => {
+				self.tc_interval = interval;
+			},
+			("hello_interval", None) | ("tc_interval", None) => {
+				println!("invalid {} value", key);
+			},
+			_ => {}
+		}
+		Ok(())
+	}
+
+	fn reset(&mut self, len: usize, _rng: &mut StdRng) {
+		self.nodes = (0..len).map(|id| Node::new(id as ID)).collect();
+		self.time = 0;
+	}
+
+	fn step(&mut self, io: &mut Io) {
+		self.time += 1;
+
+		let time = self.time;
+		let neighbor_hold = 3 * self.hello_interval;
+		let topology_hold = 3 * self.tc_interval;
+
+		for id in 0..self.nodes.len() {
+			self.nodes[id].expire(time, neighbor_hold, topology_hold);
+
+			for message in io.receive(id as ID) {
+				self.handle_message(id, &message);
+			}
+
+			let node = &mut self.nodes[id];
+			node.select_mprs();
+			node.compute_routes(io);
+
+			let mut payload = Vec::new();
+
+			if (time + id as u32).is_multiple_of(self.hello_interval) {
+				node.write_hello(&mut payload, io);
+			}
+
+			if node.is_mpr() && (time + id as u32).is_multiple_of(self.tc_interval) {
+				node.create_tc(io).write(&mut payload);
+			}
+
+			for tc in node.forward.drain(..) {
+				tc.write(&mut payload);
+			}
+
+			if !payload.is_empty() {
+				io.broadcast(id as ID, payload);
+			}
+		}
+	}
+
+	fn route(&self, packet: &TestPacket) -> Option<ID> {
+		self.nodes[packet.receiver as usize].route(packet)
+	}
+}
@@ -1,5 +1,7 @@
 
 use std::usize;
+use rand::Rng;
+use rand::rngs::StdRng;
 use crate::graph::*;
 use crate::utils::*;
 use crate::sim::{Io, RoutingAlgorithm, TestPacket};
@@ -52,15 +54,30 @@ impl Node {
 pub struct RandomRouting {
 	nodes: Vec<Node>,
 	time: u32,
+	seed: u64,
 }
 
 impl RandomRouting {
 	pub fn new() -> Self {
 		Self {
 			nodes: vec![Node::new(); 42],
-			time: 0
+			time: 0,
+			seed: 0
 		}
 	}
+
+	// pseudo random number from the packet, so that route() stays reproducible
+	fn random(&self, packet: &TestPacket) -> u64 {
+		let mut x = self.seed;
+		for v in &[packet.transmitter, packet.receiver, packet.source, packet.destination] {
+			// splitmix64
+			x = x.wrapping_add(*v as u64).wrapping_add(0x9E3779B97F4A7C15);
+			x = (x ^ (x >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+			x = (x ^ (x >> 27)).wrapping_mul(0x94D049BB133111EB);
+			x = x ^ (x >> 31);
+		}
+		x
+	}
 }
 
 impl RoutingAlgorithm for RandomRouting
@@ -78,9 +95,10 @@ impl RoutingAlgorithm for RandomRouting
 		Ok(())
 	}
 
-	fn reset(&mut self, len: usize) {
+	fn reset(&mut self, len: usize, rng: &mut StdRng) {
 		self.nodes = vec![Node::new(); len];
 		self.time = 0;
+		self.seed = rng.gen::<u64>();
 	}
 
 	fn step(&mut self, io: &mut Io) {
@@ -100,7 +118,7 @@ impl RoutingAlgorithm for RandomRouting
 	fn route(&self, packet: &TestPacket) -> Option<ID> {
 		let from = packet.receiver;
 		let neighbors = &self.nodes[from as usize].neighbors;
-		let rnd = rand::random::<usize>();
+		let rnd = self.random(packet) as usize;
 		let len = neighbors.len();
 
 		if len > 0 {
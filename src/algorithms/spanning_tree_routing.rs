@@ -1,6 +1,8 @@
 
 use std::usize;
 
+use rand::Rng;
+use rand::rngs::StdRng;
 use crate::graph::ID;
 use crate::sim::{Io, TestPacket, RoutingAlgorithm};
 use crate::utils::vec_filter;
@@ -250,7 +252,7 @@ impl RoutingAlgorithm for SpanningTreeRouting {
 		Ok(())
 	}
 
-	fn reset(&mut self, len: usize) {
+	fn reset(&mut self, len: usize, rng: &mut StdRng) {
 		self.nodes = vec![Node::new(); len];
 		self.packets = vec![Packet::new(); len];
 
@@ -263,9 +265,9 @@ impl RoutingAlgorithm for SpanningTreeRouting {
 			false
 		}
 
-		fn unique_rnd_id(nodes: &Vec<Node>) -> u32 {
+		fn unique_rnd_id(nodes: &Vec<Node>, rng: &mut StdRng) -> u32 {
 			loop {
-				let id = rand::random::<u32>() % (nodes.len() as u32 * 2);
+				let id = rng.gen::<u32>() % (nodes.len() as u32 * 2);
 				if !contains(&nodes, id) {
 					return id;
 				}
@@ -275,8 +277,8 @@ impl RoutingAlgorithm for SpanningTreeRouting {
 		// Assign random numbers.
 		// Avoid edges case for now when the ids are not unique
 		for i in 0..len {
-			//let id = unique_rnd_id(&self.nodes);
-			let time = rand::random::<u16>() as u32;
+			//let id = unique_rnd_id(&self.nodes, rng);
+			let time = rng.gen::<u16>() as u32;
 			self.nodes[i].init(i as u32, time);
 		}
 	}
@@ -1,6 +1,7 @@
 use std::f32;
 use std::u32;
 
+use rand::rngs::StdRng;
 use crate::utils::*;
 use crate::graph::*;
 use crate::sim::{Io, RoutingAlgorithm, TestPacket};
@@ -55,7 +56,7 @@ impl Node {
 		n_next
 	}
 
-	fn update(&mut self, from_id: ID, from_pos: Vec3, time: u32) {
+	fn update(&mut self, from_id: ID, from_pos: Vec3, time: u32, rng: &mut StdRng) {
 		/*
 		fn center(ns: &Vec<Neighbor>) -> Vec3 {
 			let mut pos = Vec3::new0();
@@ -76,12 +77,12 @@ impl Node {
 			}
 		);
 
-		fn update(dist: f32, local: Vec3, remote: Vec3) -> Vec3 {
+		fn update(dist: f32, local: Vec3, remote: Vec3, rng: &mut StdRng) -> Vec3 {
 			let sensitivity = 0.25;
 			let err = dist - local.distance(&remote);
 			//let direction_of_err = local.direction(&remote);
 			let direction_of_err = if local == remote {
-				Vec3::random_unit()
+				Vec3::random_unit(rng)
 			} else {
 				local.direction(&remote)
 			};
@@ -93,7 +94,7 @@ impl Node {
 		// all nodes also are randomized 0..1000
 		let mut pos = Vec3::new(0.0, 0.0, 0.0);
 		for e in &self.neighbors {
-			pos += update(1.5, self.pos, e.pos);
+			pos += update(1.5, self.pos, e.pos, rng);
 		}
 
 		self.pos = pos * (1.0 / self.neighbors.len() as f32);
@@ -143,7 +144,7 @@ impl RoutingAlgorithm for SpringRouting
 		Ok(())
 	}
 
-	fn reset(&mut self, len: usize) {
+	fn reset(&mut self, len: usize, _rng: &mut StdRng) {
 		self.nodes = vec![Node::new(); len];
 		self.time = 0;
 	}
@@ -153,7 +154,7 @@ impl RoutingAlgorithm for SpringRouting
 /*
 		// bad idea.. but at least no crash
 		if self.nodes.len() != io.nodes_count() {
-			self.reset(io.nodes_count());
+			self.reset(io.nodes_count(), io.rng());
 		}
 */
 		// fade out old entries
@@ -165,7 +166,7 @@ impl RoutingAlgorithm for SpringRouting
 		// simulate broadcast traffic
 		for (from, to) in io.link_iter() {
 			let pos = self.nodes[from as usize].pos_old;
-			self.nodes[to as usize].update(from, pos, self.time);
+			self.nodes[to as usize].update(from, pos, self.time, io.rng());
 		}
 	}
 
@@ -1,6 +1,8 @@
 use std::f32;
 use std::u32;
 
+use rand::Rng;
+use rand::rngs::StdRng;
 use crate::utils::*;
 use crate::graph::*;
 use crate::sim::{Io, TestPacket, RoutingAlgorithm};
@@ -115,15 +117,15 @@ impl VVec {
 		&self.data
 	}
 
-	pub fn random_unit() -> VVec {
-		VVec::random_in_area(1.0).unit()
+	pub fn random_unit<R: Rng>(rng: &mut R) -> VVec {
+		VVec::random_in_area(1.0, rng).unit()
 	}
 
 	// random around in the box of (0, 0, 0)
-	pub fn random_in_area(r: f32) -> VVec {
+	pub fn random_in_area<R: Rng>(r: f32, rng: &mut R) -> VVec {
 		let mut ret = VVec::new();
 		for i in 0..ret.data.len() {
-			ret.data[i] = (2.0 * rand::random::<f32>() - 1.0) * r;
+			ret.data[i] = (2.0 * rng.gen::<f32>() - 1.0) * r;
 		}
 		ret
 	}
@@ -247,7 +249,7 @@ impl Node {
 		n_next
 	}
 
-	fn update(&mut self, from_id: ID, from_pos: VVec, from_error: f32, time: u32, rtt: f32) {
+	fn update(&mut self, from_id: ID, from_pos: VVec, from_error: f32, time: u32, rtt: f32, rng: &mut StdRng) {
 		vec_add_entry(&mut self.neighbors,
 			&Neighbor {
 				id: from_id,
@@ -257,7 +259,7 @@ impl Node {
 			}
 		);
 
-		self.vivaldi_update(&from_pos, from_error, rtt);
+		self.vivaldi_update(&from_pos, from_error, rtt, rng);
 	}
 
 	fn cut_old_pos(&mut self) {
@@ -278,7 +280,7 @@ impl Node {
 	}
 
 	// Vivaldi algorithm
-	fn vivaldi_update(&mut self, pos: &VVec, error: f32, rtt: f32) {
+	fn vivaldi_update(&mut self, pos: &VVec, error: f32, rtt: f32, rng: &mut StdRng) {
 		//let rtt = self.rtt;
 		let ce = 0.25;
 		let cc = 0.25;
@@ -308,7 +310,7 @@ impl Node {
 		// Choose random direction if both positions are identical
 		let direction = if ab.is_near_null(0.01) {
 			//println!("random direction");
-			VVec::random_unit()
+			VVec::random_unit(rng)
 		} else {
 			ab
 		};
@@ -352,7 +354,24 @@ impl VivaldiRouting {
 
 impl RoutingAlgorithm for VivaldiRouting
 {
-	fn reset(&mut self, len: usize) {
+	// coordinate change relative to the distance from the origin,
+	// comparable to the fraction of changed routes
+	fn convergence(&self) -> Option<f32> {
+		let mut change = 0.0;
+		let mut length = 0.0;
+		for node in &self.nodes {
+			change += node.pos_old.distance(&node.pos);
+			length += node.pos.length();
+		}
+
+		if length > 0.0 {
+			Some(change / length)
+		} else {
+			Some(0.0)
+		}
+	}
+
+	fn reset(&mut self, len: usize, _rng: &mut StdRng) {
 		self.nodes = vec![Node::new(); len];
 		self.time = 0;
 	}
@@ -414,7 +433,7 @@ impl RoutingAlgorithm for VivaldiRouting
 		// simulate broadcast traffic
 		for (from, to) in io.link_iter() {
 			let pos_old = self.nodes[from as usize].pos_old;
-			self.nodes[to as usize].update(from, pos_old, 1.0, self.time, self.rtt);
+			self.nodes[to as usize].update(from, pos_old, 1.0, self.time, self.rtt, io.rng());
 		}
 	}
 
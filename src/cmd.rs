@@ -8,20 +8,21 @@ use std::io::{BufRead, BufReader};
 use std::io::{Read, Write};
 use std::net::{TcpListener, TcpStream};
 
-use crate::eval_paths::EvalPaths;
+use crate::eval_paths::{EvalPaths, Outcome};
 use crate::debug_path::DebugPath;
-use crate::graph::Graph;
+use crate::dijkstra::Dijkstra;
+use crate::graph::{Graph, Metric};
 use crate::progress::Progress;
-use crate::sim::{Io, GlobalState, RoutingAlgorithm};
-use crate::algorithms::vivaldi_routing::VivaldiRouting;
-use crate::algorithms::random_routing::RandomRouting;
-use crate::algorithms::spring_routing::SpringRouting;
-use crate::algorithms::genetic_routing::GeneticRouting;
-use crate::algorithms::spanning_tree_routing::SpanningTreeRouting;
+use rand::rngs::StdRng;
+use crate::sim::{GlobalState, RoutingAlgorithm, RadioModel};
+use crate::algorithms::{ALGORITHMS, find_algorithm};
 use crate::importer::import_file;
-use crate::exporter::export_file;
+use crate::exporter::{export_file, export_format, export_netjson};
+use crate::experiment::{Sweep, Run, RunResult, export_results};
 use crate::utils::{fmt_duration, DEG2KM, MyError};
-use crate::movements::Movements;
+use crate::movements::Model;
+use crate::propagation;
+use crate::failures::Target;
 
 
 #[derive(PartialEq)]
@@ -136,17 +137,50 @@ enum Command {
 	AddStar(u32),
 	AddLattice4(u32, u32),
 	AddLattice8(u32, u32),
+	AddGnp(u32, f32),
+	AddSmallWorld(u32, u32, f32),
+	AddScaleFree(u32, u32),
+	AddRandomGeometric(u32, f32),
+	AddCommunityMesh(u32, u32, u32),
 	Positions(bool),
 	RemoveNodes(Vec<u32>),
 	ConnectNodes(Vec<u32>),
 	DisconnectNodes(Vec<u32>),
 	SimStep(u32),
+	SimUntilConverged(u32, f32),
+	LinkDelay(u32, Option<(u32, u32)>),
+	Metric(Option<Metric>),
+	LinkCost(u32, u32, u16),
+	LinkBandwidth(u32, u32, u16),
 	Run(String),
-	Import(String),
+	Import(String, Option<String>),
 	ExportPath(Option<String>),
+	ExportStats(String),
+	ExportGraph(String),
 	MoveNode(u32, f32, f32, f32),
 	MoveNodes(f32, f32, f32),
 	MoveTo(f32, f32, f32),
+	Mobility(Option<u32>, Model, f32, u32),
+	MobilityArea(f32, f32, f32, f32),
+	Radio(Option<(f32, u32)>),
+	Propagation(Option<propagation::Model>),
+	RadioPower(f32, f32),
+	Seed(Option<u64>),
+	Threads(Option<usize>),
+	Apsp(Option<bool>),
+	TestDetails(Option<bool>),
+	MaxStretch(Option<u32>),
+	Record(Option<(u32, u32)>),
+	Crash(u32, u32, u32),
+	Recover(u32),
+	Flap(u32, u32, u32, u32),
+	Partition(f32, f32, f32, f32, u32, u32),
+	FailuresRandom(f32, f32, u32),
+	Failures(bool),
+	FailuresLog(Option<String>),
+	ExportRecord(String),
+	ExportReport(String),
+	Experiment(String, Option<String>),
 }
 
 #[derive(Clone, Copy, PartialEq)]
@@ -175,27 +209,74 @@ enum Cid {
 	AddStar,
 	AddLattice4,
 	AddLattice8,
+	AddGnp,
+	AddSmallWorld,
+	AddScaleFree,
+	AddRandomGeometric,
+	AddCommunityMesh,
 	Positions,
 	RemoveNodes,
 	ConnectNodes,
 	DisconnectNodes,
 	SimStep,
+	SimUntilConverged,
+	LinkDelay,
+	Metric,
+	LinkCost,
+	LinkBandwidth,
 	Run,
 	Import,
 	ExportPath,
+	ExportStats,
+	ExportGraph,
 	MoveNode,
 	MoveNodes,
-	MoveTo
+	MoveTo,
+	Mobility,
+	MobilityNode,
+	MobilityArea,
+	Radio,
+	Propagation,
+	RadioPower,
+	Seed,
+	Threads,
+	Apsp,
+	TestDetails,
+	MaxStretch,
+	Record,
+	Crash,
+	Recover,
+	Flap,
+	Partition,
+	FailuresRandom,
+	Failures,
+	FailuresLog,
+	ExportRecord,
+	ExportReport,
+	Experiment
 }
 
 
 const COMMANDS: &'static [(&'static str, Cid)] = &[
 	("algo [<algorithm>]                 Get or set given algorithm.", Cid::Algorithm),
 	("sim_step [<steps>]                 Run simulation steps. Default is 1.", Cid::SimStep),
+	("sim_until_converged [<max>] [<th>] Run simulation steps until routes are stable (max steps, threshold).", Cid::SimUntilConverged),
 	("sim_reset                          Reset simulation.", Cid::ResetSim),
 	("sim_info                           Show simulator information.", Cid::SimInfo),
 	("progress [<true|false>]            Show simulation progress.", Cid::Progress),
+	("seed [<number>]                    Get or set seed of the random number generator.", Cid::Seed),
+	("record [<interval>] [<samples>]    Test every interval simulation steps and record the results. 0 disables.", Cid::Record),
+	("export_record <file>               Export recorded results as CSV or JSON.", Cid::ExportRecord),
+	("link_delay <steps> [<from> <to>]   Set delay of management packets for all links or a single link.", Cid::LinkDelay),
+	("metric [<hop|etx|ett|custom>]      Get or set the metric used for link costs.", Cid::Metric),
+	("link_cost <from> <to> <cost>       Set link cost for the custom metric.", Cid::LinkCost),
+	("link_bandwidth <from> <to> <mbits> Set link bandwidth for the ETT metric.", Cid::LinkBandwidth),
+	("threads [<count>]                  Get or set worker threads of the test command. 0 for all cpus.", Cid::Threads),
+	("apsp [<true|false>]                Precompute shortest distances of all pairs for the test command.", Cid::Apsp),
 	("test [<samples>]                   Test routing algorithm with (test packets arrived, path stretch).", Cid::Test),
+	("max_stretch [<stretch>]            Get or set path stretch at which test packets are dropped.", Cid::MaxStretch),
+	("test_details [<true|false>]        Record every tested path with its failure reason.", Cid::TestDetails),
+	("export_report <file>               Export recorded paths of the last test as CSV or JSON.", Cid::ExportReport),
 	("debug_init <from> <to>             Debug a path step wise.", Cid::Debug),
 	("debug_step [<steps>]               Perform step on path.", Cid::DebugStep),
 	("", Cid::Error),
@@ -209,21 +290,43 @@ const COMMANDS: &'static [(&'static str, Cid)] = &[
 	("tree <node_count> [<inter_count>]  Add a tree structure of nodes with interconnections", Cid::AddTree),
 	("lattice4 <x_xount> <y_count>       Create a lattice structure of squares.", Cid::AddLattice4),
 	("lattice8 <x_xount> <y_count>       Create a lattice structure of squares and diagonal connections.", Cid::AddLattice8),
+	("gnp <node_count> <probability>     Add a random graph, each pair of nodes is connected with probability.", Cid::AddGnp),
+	("small_world <node_count> <k> <beta> Add a Watts-Strogatz small world ring with k neighbors, links are rewired with probability beta.", Cid::AddSmallWorld),
+	("scale_free <node_count> <m>        Add a Barabasi-Albert scale free graph, new nodes attach to m nodes.", Cid::AddScaleFree),
+	("random_geometric <node_count> <radius> Add randomly placed nodes, connect nodes closer than radius (relative to area width).", Cid::AddRandomGeometric),
+	("community_mesh <node_count> [<density>] [<cluster_size>] Add neighborhoods of nodes with backbone and gateway nodes.", Cid::AddCommunityMesh),
 	("remove_nodes <node_list>           Remove nodes. Node list is a comma separated list of node ids.", Cid::RemoveNodes),
 	("connect_nodes <node_list>          Connect nodes. Node list is a comma separated list of node ids.", Cid::ConnectNodes),
 	("disconnect_nodes <node_list>       Disconnect nodes. Node list is a comma separated list of node ids.", Cid::DisconnectNodes),
 	("remove_unconnected                 Remove nodes without any connections.", Cid::RemoveUnconnected),
 	("", Cid::Error),
+	("crash <node> [<delay>] [<duration>] Crash node after delay steps for duration steps (0 for ever).", Cid::Crash),
+	("recover <node>                     Recover crashed node in the next step.", Cid::Recover),
+	("flap <from> <to> <period> [<duration>] Toggle link down/up every period steps.", Cid::Flap),
+	("partition <x1> <y1> <x2> <y2> [<delay>] [<duration>] Cut links into and out of an area.", Cid::Partition),
+	("failures_random <crash_rate> <flap_rate> [<duration>] Random node crashes and link failures per step.", Cid::FailuresRandom),
+	("failures [clear]                   Show or remove all failures.", Cid::Failures),
+	("failures_log [<file>]              Show or export the log of failure events.", Cid::FailuresLog),
+	("", Cid::Error),
 	("positions <true|false>             Enable geo positions.", Cid::Positions),
 	("move_node <node_id> <x> <y> <z>    Move a node by x/y/z (in km).", Cid::MoveNode),
 	("move_nodes <x> <y> <z>             Move all nodes by x/y/z (in km).", Cid::MoveNodes),
 	("move_to <x> <y> <z>                Move all nodes to x/y/z (in degrees).", Cid::MoveTo),
 	("rnd_pos <range>                    Randomize node positions in an area with width (in km) around node center.", Cid::RandomizePositions),
 	("connect_in_range <range>           Connect all nodes in range of less then range (in km).", Cid::ConnectInRange),
+	("mobility <model> [<speed>] [<pause>] Set mobility model of all nodes: static, waypoint, walk, gauss_markov. Speed is in km per step.", Cid::Mobility),
+	("mobility_node <node_id> <model> [<speed>] [<pause>] Set mobility model of a single node.", Cid::MobilityNode),
+	("mobility_area <x1> <y1> <x2> <y2>  Set area nodes move in. Default is the area of all nodes.", Cid::MobilityArea),
+	("radio [<range>] [<interval>]       Update links from node positions every interval steps. Range 0 disables.", Cid::Radio),
+	("propagation [<model>] [<args>]     Get or set propagation model: unit_disk, log_distance [<exponent>], two_ray [<antenna_height>], shadowing [<exponent>] [<sigma>].", Cid::Propagation),
+	("radio_power <tx_power> <sensitivity> Set transmission power and receiver sensitivity (in dBm).", Cid::RadioPower),
 	("", Cid::Error),
 	("run <file>                         Run commands from a script.", Cid::Run),
-	("import <file>                      Import a graph as JSON file.", Cid::Import),
+	("experiment <file> [<output>]       Run a parameter sweep and write results as CSV or JSON file.", Cid::Experiment),
+	("import <file> [<positions>]        Import a graph as NetJSON, GraphML, GML, DOT file or edge list with positions file.", Cid::Import),
 	("export [<file>]                    Get or set graph export file.", Cid::ExportPath),
+	("export_stats <file>                Export management traffic statistics as JSON file.", Cid::ExportStats),
+	("export_graph <file>                Export graph as NetJSON file.", Cid::ExportGraph),
 	("show_mst                           Mark the minimum spanning tree.", Cid::ShowMinimumSpanningTree),
 	("crop_mst                           Only leave the minimum spanning tree.", Cid::CropMinimumSpanningTree),
 	("exit                               Exit simulator.", Cid::Exit),
@@ -326,6 +429,47 @@ fn parse_command(input: &str) -> Command {
 				1
 			})
 		},
+		Cid::SimUntilConverged => {
+			let mut iter1 = iter.clone();
+			if let (Some(max_steps), Some(threshold)) = scan!(iter1, u32, f32) {
+				Command::SimUntilConverged(max_steps, threshold)
+			} else if let (Some(max_steps),) = scan!(iter, u32) {
+				Command::SimUntilConverged(max_steps, 0.01)
+			} else {
+				Command::SimUntilConverged(1000, 0.01)
+			}
+		},
+		Cid::LinkDelay => {
+			// either all link arguments or none
+			match (tokens.len(), scan!(iter, u32, u32, u32)) {
+				(4, (Some(steps), Some(from), Some(to))) => Command::LinkDelay(steps, Some((from, to))),
+				(2, (Some(steps), None, None)) => Command::LinkDelay(steps, None),
+				_ => error
+			}
+		},
+		Cid::Metric => {
+			if let (Some(metric),) = scan!(iter.clone(), Metric) {
+				Command::Metric(Some(metric))
+			} else if iter.next().is_none() {
+				Command::Metric(None)
+			} else {
+				error
+			}
+		},
+		Cid::LinkCost => {
+			if let (Some(from), Some(to), Some(cost)) = scan!(iter, u32, u32, u16) {
+				Command::LinkCost(from, to, cost)
+			} else {
+				error
+			}
+		},
+		Cid::LinkBandwidth => {
+			if let (Some(from), Some(to), Some(bandwidth)) = scan!(iter, u32, u32, u16) {
+				Command::LinkBandwidth(from, to, bandwidth)
+			} else {
+				error
+			}
+		},
 		Cid::Run => {
 			if let (Some(path),) = scan!(iter, String) {
 				Command::Run(path)
@@ -333,9 +477,19 @@ fn parse_command(input: &str) -> Command {
 				error
 			}
 		},
+		Cid::Experiment => {
+			let mut iter1 = iter.clone();
+			if let (Some(path), Some(output)) = scan!(iter1, String, String) {
+				Command::Experiment(path, Some(output))
+			} else if let (Some(path),) = scan!(iter, String) {
+				Command::Experiment(path, None)
+			} else {
+				error
+			}
+		},
 		Cid::Import => {
-			if let (Some(path),) = scan!(iter, String) {
-				Command::Import(path)
+			if let (Some(path), positions) = scan!(iter, String, String) {
+				Command::Import(path, positions)
 			} else {
 				error
 			}
@@ -347,6 +501,20 @@ fn parse_command(input: &str) -> Command {
 				Command::ExportPath(None)
 			}
 		},
+		Cid::ExportGraph => {
+			if let (Some(path),) = scan!(iter, String) {
+				Command::ExportGraph(path)
+			} else {
+				error
+			}
+		},
+		Cid::ExportStats => {
+			if let (Some(path),) = scan!(iter, String) {
+				Command::ExportStats(path)
+			} else {
+				error
+			}
+		},
 		Cid::MoveNodes => {
 			if let (Some(x), Some(y), Some(z)) = scan!(iter, f32, f32, f32) {
 				Command::MoveNodes(x, y, z)
@@ -404,6 +572,41 @@ fn parse_command(input: &str) -> Command {
 				error
 			}
 		},
+		Cid::AddGnp => {
+			if let (Some(count), Some(p)) = scan!(iter, u32, f32) {
+				Command::AddGnp(count, p)
+			} else {
+				error
+			}
+		},
+		Cid::AddSmallWorld => {
+			if let (Some(count), Some(k), Some(beta)) = scan!(iter, u32, u32, f32) {
+				Command::AddSmallWorld(count, k, beta)
+			} else {
+				error
+			}
+		},
+		Cid::AddScaleFree => {
+			if let (Some(count), Some(m)) = scan!(iter, u32, u32) {
+				Command::AddScaleFree(count, m)
+			} else {
+				error
+			}
+		},
+		Cid::AddRandomGeometric => {
+			if let (Some(count), Some(radius)) = scan!(iter, u32, f32) {
+				Command::AddRandomGeometric(count, radius)
+			} else {
+				error
+			}
+		},
+		Cid::AddCommunityMesh => {
+			if let (Some(count), density, cluster_size) = scan!(iter, u32, u32, u32) {
+				Command::AddCommunityMesh(count, density.unwrap_or(3), cluster_size.unwrap_or(25))
+			} else {
+				error
+			}
+		},
 		Cid::Positions => {
 			if let (Some(enable),) = scan!(iter, bool) {
 				Command::Positions(enable)
@@ -463,6 +666,183 @@ fn parse_command(input: &str) -> Command {
 				error
 			}
 		},
+		Cid::Mobility => {
+			let mut iter1 = iter.clone();
+			let mut iter2 = iter.clone();
+			if let (Some(model), Some(speed), Some(pause)) = scan!(iter1, Model, f32, u32) {
+				Command::Mobility(None, model, speed, pause)
+			} else if let (Some(model), Some(speed)) = scan!(iter2, Model, f32) {
+				Command::Mobility(None, model, speed, 0)
+			} else if let (Some(model),) = scan!(iter, Model) {
+				Command::Mobility(None, model, 1.0, 0)
+			} else {
+				error
+			}
+		},
+		Cid::MobilityNode => {
+			let mut iter1 = iter.clone();
+			let mut iter2 = iter.clone();
+			if let (Some(id), Some(model), Some(speed), Some(pause)) = scan!(iter1, u32, Model, f32, u32) {
+				Command::Mobility(Some(id), model, speed, pause)
+			} else if let (Some(id), Some(model), Some(speed)) = scan!(iter2, u32, Model, f32) {
+				Command::Mobility(Some(id), model, speed, 0)
+			} else if let (Some(id), Some(model)) = scan!(iter, u32, Model) {
+				Command::Mobility(Some(id), model, 1.0, 0)
+			} else {
+				error
+			}
+		},
+		Cid::MobilityArea => {
+			if let (Some(x1), Some(y1), Some(x2), Some(y2)) = scan!(iter, f32, f32, f32, f32) {
+				Command::MobilityArea(x1, y1, x2, y2)
+			} else {
+				error
+			}
+		},
+		Cid::Radio => {
+			let mut iter1 = iter.clone();
+			if let (Some(range), Some(interval)) = scan!(iter1, f32, u32) {
+				Command::Radio(Some((range, interval)))
+			} else if let (Some(range),) = scan!(iter, f32) {
+				Command::Radio(Some((range, 1)))
+			} else {
+				Command::Radio(None)
+			}
+		},
+		Cid::Propagation => {
+			if let Some(name) = tokens.get(1) {
+				// optional numeric arguments
+				let args : Vec<f32> = tokens.iter().skip(2).filter_map(|arg| arg.parse::<f32>().ok()).collect();
+				let arg = |i: usize, default: f32| *args.get(i).unwrap_or(&default);
+				match *name {
+					"unit_disk" => Command::Propagation(Some(propagation::Model::UnitDisk)),
+					"log_distance" => Command::Propagation(Some(propagation::Model::LogDistance {
+						exponent: arg(0, 3.0)
+					})),
+					"two_ray" => Command::Propagation(Some(propagation::Model::TwoRayGround {
+						height: arg(0, 1.5)
+					})),
+					"shadowing" => Command::Propagation(Some(propagation::Model::Shadowing {
+						exponent: arg(0, 3.0),
+						sigma: arg(1, 4.0)
+					})),
+					_ => Command::Error(format!("Unknown propagation model: {}", name))
+				}
+			} else {
+				Command::Propagation(None)
+			}
+		},
+		Cid::RadioPower => {
+			if let (Some(tx_power), Some(sensitivity)) = scan!(iter, f32, f32) {
+				Command::RadioPower(tx_power, sensitivity)
+			} else {
+				error
+			}
+		},
+		Cid::Seed => {
+			if let (Some(seed),) = scan!(iter, u64) {
+				Command::Seed(Some(seed))
+			} else {
+				Command::Seed(None)
+			}
+		},
+		Cid::Threads => {
+			if let (Some(threads),) = scan!(iter, usize) {
+				Command::Threads(Some(threads))
+			} else {
+				Command::Threads(None)
+			}
+		},
+		Cid::Record => {
+			let mut iter1 = iter.clone();
+			if let (Some(interval), Some(samples)) = scan!(iter1, u32, u32) {
+				Command::Record(Some((interval, samples)))
+			} else if let (Some(interval),) = scan!(iter, u32) {
+				Command::Record(Some((interval, 1000)))
+			} else {
+				Command::Record(None)
+			}
+		},
+		Cid::ExportRecord => {
+			if let (Some(path),) = scan!(iter, String) {
+				Command::ExportRecord(path)
+			} else {
+				error
+			}
+		},
+		Cid::Crash => {
+			if let (Some(id), delay, duration) = scan!(iter, u32, u32, u32) {
+				Command::Crash(id, delay.unwrap_or(0), duration.unwrap_or(0))
+			} else {
+				error
+			}
+		},
+		Cid::Recover => {
+			if let (Some(id),) = scan!(iter, u32) {
+				Command::Recover(id)
+			} else {
+				error
+			}
+		},
+		Cid::Flap => {
+			if let (Some(from), Some(to), Some(period), duration) = scan!(iter, u32, u32, u32, u32) {
+				Command::Flap(from, to, period, duration.unwrap_or(0))
+			} else {
+				error
+			}
+		},
+		Cid::Partition => {
+			if let (Some(x1), Some(y1), Some(x2), Some(y2), delay, duration) = scan!(iter, f32, f32, f32, f32, u32, u32) {
+				Command::Partition(x1, y1, x2, y2, delay.unwrap_or(0), duration.unwrap_or(0))
+			} else {
+				error
+			}
+		},
+		Cid::FailuresRandom => {
+			if let (Some(crash_rate), Some(flap_rate), duration) = scan!(iter, f32, f32, u32) {
+				Command::FailuresRandom(crash_rate, flap_rate, duration.unwrap_or(10))
+			} else {
+				error
+			}
+		},
+		Cid::Failures => {
+			Command::Failures(iter.next() == Some(&"clear"))
+		},
+		Cid::FailuresLog => {
+			if let (Some(path),) = scan!(iter, String) {
+				Command::FailuresLog(Some(path))
+			} else {
+				Command::FailuresLog(None)
+			}
+		},
+		Cid::MaxStretch => {
+			if let (Some(max_stretch),) = scan!(iter, u32) {
+				Command::MaxStretch(Some(max_stretch))
+			} else {
+				Command::MaxStretch(None)
+			}
+		},
+		Cid::TestDetails => {
+			if let (Some(detailed),) = scan!(iter, bool) {
+				Command::TestDetails(Some(detailed))
+			} else {
+				Command::TestDetails(None)
+			}
+		},
+		Cid::ExportReport => {
+			if let (Some(path),) = scan!(iter, String) {
+				Command::ExportReport(path)
+			} else {
+				error
+			}
+		},
+		Cid::Apsp => {
+			if let (Some(apsp),) = scan!(iter, bool) {
+				Command::Apsp(Some(apsp))
+			} else {
+				Command::Apsp(None)
+			}
+		},
 		Cid::Error => {
 			if cmd.is_empty() {
 				Command::Ignore
@@ -475,13 +855,22 @@ fn parse_command(input: &str) -> Command {
 	}
 }
 
+fn print_algorithms(out: &mut dyn std::fmt::Write) -> Result<(), MyError> {
+	writeln!(out)?;
+	for entry in ALGORITHMS {
+		writeln!(out, "  {:<15} {}", entry.name, entry.description)?;
+	}
+	Ok(())
+}
+
 fn print_help(out: &mut std::fmt::Write) -> Result<(), MyError> {
 	for item in COMMANDS {
 		if item.1 != Cid::Error {
 			writeln!(out, "{}", item.0)?;
 		}
 	}
-	Ok(())
+	write!(out, "\nalgorithms:")?;
+	print_algorithms(out)
 }
 
 fn cmd_handler(out: &mut std::fmt::Write, sim: &mut GlobalState, input: &str, call: AllowRecursiveCall) -> Result<(), MyError> {
@@ -545,8 +934,13 @@ fn cmd_handler(out: &mut std::fmt::Write, sim: &mut GlobalState, input: &str, ca
 			let link_count = sim.graph.link_count();
 			let avg_node_degree = sim.graph.get_avg_node_degree();
 
-			writeln!(out, "nodes: {}, links: {}", node_count, link_count)?;
+			writeln!(out, "nodes: {}, links: {}, metric: {}", node_count, link_count, sim.graph.metric().name())?;
 			writeln!(out, "locations: {}, metadata: {}", sim.locations.data.len(), sim.meta.data.len())?;
+			write!(out, "mobile nodes: {}", sim.movements.count())?;
+			if let Some(area) = sim.movements.get_area() {
+				write!(out, ", area: ({}, {}) - ({}, {})", area[0], area[1], area[2], area[3])?;
+			}
+			writeln!(out)?;
 			writeln!(out, "average node degree: {}", avg_node_degree)?;
 /*
 			if (verbose) {
@@ -560,13 +954,27 @@ fn cmd_handler(out: &mut std::fmt::Write, sim: &mut GlobalState, input: &str, ca
 */
 		},
 		Command::SimInfo => {
-			write!(out, " algo: ")?;
+			write!(out, " algo: {} (", sim.algorithm_name)?;
 			sim.algorithm.get("name", out)?;
+			writeln!(out, ")")?;
+			if let Some(entry) = find_algorithm(sim.algorithm_name) {
+				writeln!(out, " description: {}", entry.description)?;
+			}
 
-			writeln!(out, "\n steps: {}", sim.sim_steps)?;
+			writeln!(out, " steps: {}", sim.sim_steps)?;
+			writeln!(out, " seed: {}", sim.seed)?;
+			writeln!(out, " packets in transit: {}", sim.queues.pending())?;
+			writeln!(out, " management packets: {}, bytes: {}, costs: {}",
+				sim.traffic.total.packets, sim.traffic.total.bytes, sim.traffic.total.costs)?;
+			let steps = sim.traffic.step_count();
+			if steps > 0 {
+				writeln!(out, " per step: {:.1} packets, {:.1} bytes",
+					sim.traffic.total.packets as f32 / steps as f32,
+					sim.traffic.total.bytes as f32 / steps as f32)?;
+			}
 		},
 		Command::ClearGraph => {
-			sim.graph.clear();
+			sim.clear();
 			do_init = true;
 			writeln!(out, "done")?;
 		},
@@ -580,16 +988,14 @@ fn cmd_handler(out: &mut std::fmt::Write, sim: &mut GlobalState, input: &str, ca
 		Command::SimStep(count) => {
 			let mut progress = Progress::new();
 			let now = Instant::now();
-			let mut io = Io::new(&sim.graph);
+			let events = sim.failures.log().len();
 
 			for step in 0..count {
 				if sim.abort_simulation {
 					break;
 				}
 
-				sim.algorithm.step(&mut io);
-				sim.movements.step(&mut sim.locations);
-				sim.sim_steps += 1;
+				sim.step();
 
 				if sim.show_progress {
 					progress.update((count + 1) as usize, step as usize);
@@ -598,28 +1004,101 @@ fn cmd_handler(out: &mut std::fmt::Write, sim: &mut GlobalState, input: &str, ca
 
 			let duration = now.elapsed();
 
-			writeln!(out, "Run {} simulation steps, duration: {}", count, fmt_duration(duration))?;
+			write!(out, "Run {} simulation steps, duration: {}", count, fmt_duration(duration))?;
+			if sim.failures.log().len() > events {
+				write!(out, ", failure events: {}", sim.failures.log().len() - events)?;
+			}
+			writeln!(out)?;
+		},
+		Command::SimUntilConverged(max_steps, threshold) => {
+			let now = Instant::now();
+			let (converged, steps) = sim.run_until_converged(max_steps, threshold);
+			let duration = now.elapsed();
+
+			if let Some(converged) = converged {
+				writeln!(out, "Converged after {} simulation steps, duration: {}", converged, fmt_duration(duration))?;
+			} else {
+				writeln!(out, "Not converged after {} simulation steps, duration: {}", steps, fmt_duration(duration))?;
+			}
+		},
+		Command::Metric(metric) => {
+			if let Some(metric) = metric {
+				sim.graph.set_metric(metric);
+			}
+			writeln!(out, "metric: {}", sim.graph.metric().name())?;
+		},
+		Command::LinkCost(from, to, cost) => {
+			if sim.graph.set_link_cost(from, to, cost) {
+				writeln!(out, "link cost {} => {}: {}", from, to, cost)?;
+			} else {
+				writeln!(out, "link not found: {} => {}", from, to)?;
+			}
+		},
+		Command::LinkBandwidth(from, to, bandwidth) => {
+			if sim.graph.set_link_bandwidth(from, to, bandwidth) {
+				writeln!(out, "link bandwidth {} => {}: {}", from, to, bandwidth)?;
+			} else {
+				writeln!(out, "link not found: {} => {}", from, to)?;
+			}
+		},
+		Command::LinkDelay(steps, link) => {
+			if let Some((from, to)) = link {
+				sim.queues.set_delay(from, to, steps);
+				writeln!(out, "link delay {} => {}: {}", from, to, steps)?;
+			} else {
+				sim.queues.set_default_delay(steps);
+				writeln!(out, "link delay: {}", steps)?;
+			}
 		},
 		Command::Test(samples) => {
-			fn run_test(out: &mut std::fmt::Write, test: &mut EvalPaths, graph: &Graph, algo: &Box<RoutingAlgorithm>, samples: u32)
+			fn run_test(out: &mut dyn std::fmt::Write, test: &mut EvalPaths, graph: &Graph, algo: &dyn RoutingAlgorithm, samples: u32, rng: &mut StdRng)
 				-> Result<(), std::fmt::Error>
 			{
 				test.clear();
-				test.run_samples(graph, |p| algo.route(&p), samples as usize);
-				writeln!(out, "samples: {},  arrived: {:.1}, stretch: {}, duration: {}",
+				test.run_samples(graph, |p| algo.route(p), samples as usize, rng);
+				writeln!(out, "samples: {},  arrived: {:.1}, stretch: {}, loops: {}, duration: {}",
 					samples,
-					test.arrived(), test.stretch(),
+					test.arrived(), test.stretch(), test.loops(),
 					fmt_duration(test.duration())
 				)
 			}
 			sim.test.show_progress(sim.show_progress);
-			run_test(out, &mut sim.test, &sim.graph, &sim.algorithm, samples)?;
+			if sim.algorithm.is_reactive() {
+				// route discoveries change the state of reactive algorithms
+				let test = &mut sim.test;
+				test.run_samples_reactive(&sim.graph, sim.algorithm.as_mut(),
+					&mut sim.queues, &mut sim.rng, samples as usize);
+				writeln!(out, "samples: {},  arrived: {:.1}, stretch: {}, loops: {}, duration: {}",
+					samples,
+					test.arrived(), test.stretch(), test.loops(),
+					fmt_duration(test.duration())
+				)?;
+				writeln!(out, "discoveries: {}, latency: {:.1} steps, overhead: {:.1} packets ({:.1} bytes)",
+					test.discoveries(), test.discovery_latency(),
+					test.discovery_overhead(), test.discovery_overhead_bytes()
+				)?;
+			} else {
+				run_test(out, &mut sim.test, &sim.graph, sim.algorithm.as_ref(), samples, &mut sim.rng)?;
+			}
+			if sim.test.detailed() {
+				let test = &sim.test;
+				writeln!(out, "no next hop: {}, invalid next hop: {}, loop: {}, ttl exceeded: {}",
+					test.outcome_count(Outcome::NoNextHop), test.outcome_count(Outcome::InvalidNextHop),
+					test.outcome_count(Outcome::Loop), test.outcome_count(Outcome::TtlExceeded)
+				)?;
+			}
 		},
 		Command::Debug(from, to) => {
 			let node_count = sim.graph.node_count() as u32;
-			if (from < node_count) && (from < node_count) {
+			if (from < node_count) && (to < node_count) {
 				sim.debug_path.init(from, to);
 				writeln!(out, "Init path debugger: {} => {}", from, to)?;
+				if let Some(path) = Dijkstra::new().find_shortest_path(&sim.graph, from, to) {
+					let path : Vec<String> = path.iter().map(|id| id.to_string()).collect();
+					writeln!(out, "shortest path: {}", path.join(" => "))?;
+				} else {
+					writeln!(out, "shortest path: none")?;
+				}
 			} else {
 				writeln!(out, "Invalid path: {} => {}", from, to)?;
 			}
@@ -628,17 +1107,22 @@ fn cmd_handler(out: &mut std::fmt::Write, sim: &mut GlobalState, input: &str, ca
 			fn run_test(out: &mut std::fmt::Write, debug_path: &mut DebugPath, graph: &Graph, algo: &Box<RoutingAlgorithm>)
 				-> Result<(), MyError>
 			{
-				debug_path.step(out, graph, |p| algo.route(&p))
+				debug_path.step(out, graph, |p| algo.route(p))
 			}
 
 			for _ in 0..steps {
 				run_test(out, &mut sim.debug_path, &sim.graph, &sim.algorithm)?;
 			}
 		}
-		Command::Import(ref path) => {
-			import_file(&mut sim.graph, Some(&mut sim.locations), Some(&mut sim.meta), path.as_str())?;
+		Command::Import(ref path, ref positions) => {
+			let metric = sim.graph.metric();
+			import_file(&mut sim.graph, Some(&mut sim.locations), Some(&mut sim.meta), path.as_str(), positions.as_ref().map(|p| p.as_str()))?;
 			do_init = true;
 			writeln!(out, "Import done: {}", path)?;
+			if sim.graph.metric() != metric {
+				// imported link costs are used for all links
+				writeln!(out, "metric changed: {} => {}", metric.name(), sim.graph.metric().name())?;
+			}
 		},
 		Command::ExportPath(path) => {
 			if let Some(path) = path {
@@ -647,6 +1131,20 @@ fn cmd_handler(out: &mut std::fmt::Write, sim: &mut GlobalState, input: &str, ca
 
 			writeln!(out, "Export done: {}", sim.export_path)?;
 		},
+		Command::ExportGraph(path) => {
+			let mut file = File::create(&path)?;
+			let content = export_format(&sim.graph, Some(&sim.locations), Some(&sim.meta), &path)
+				.unwrap_or_else(|| export_netjson(&sim.graph, Some(&sim.locations), Some(&sim.meta)));
+			file.write_all(content.as_bytes())?;
+			writeln!(out, "Export done: {}", path)?;
+		},
+		Command::ExportStats(path) => {
+			let mut name = String::new();
+			sim.algorithm.get("name", &mut name)?;
+			let mut file = File::create(&path)?;
+			file.write_all(sim.traffic.to_json(&name).as_bytes())?;
+			writeln!(out, "Export done: {}", path)?;
+		},
 		Command::AddLine(count, close) => {
 			sim.add_line(count, close);
 			do_init = true;
@@ -673,6 +1171,26 @@ fn cmd_handler(out: &mut std::fmt::Write, sim: &mut GlobalState, input: &str, ca
 			sim.add_lattice8(x_count, y_count);
 			do_init = true;
 		},
+		Command::AddGnp(count, p) => {
+			sim.add_gnp(count, p);
+			do_init = true;
+		},
+		Command::AddSmallWorld(count, k, beta) => {
+			sim.add_small_world(count, k, beta);
+			do_init = true;
+		},
+		Command::AddScaleFree(count, m) => {
+			sim.add_scale_free(count, m);
+			do_init = true;
+		},
+		Command::AddRandomGeometric(count, radius) => {
+			sim.add_random_geometric(count, radius);
+			do_init = true;
+		},
+		Command::AddCommunityMesh(count, density, cluster_size) => {
+			sim.add_community_mesh(count, density, cluster_size);
+			do_init = true;
+		},
 		Command::Positions(enable) => {
 			if enable {
 				// add positions to node that have none
@@ -690,46 +1208,184 @@ fn cmd_handler(out: &mut std::fmt::Write, sim: &mut GlobalState, input: &str, ca
 		}
 		Command::RandomizePositions(range) => {
 			let center = sim.locations.graph_center();
-			sim.locations.randomize_positions_2d(center, range);
+			sim.locations.randomize_positions_2d(center, range, &mut sim.rng);
 		},
 		Command::ConnectInRange(range) => {
 			sim.connect_in_range(range);
 		},
-		Command::Algorithm(algo) => {
-			if let Some(algo) = algo {
-				match algo.as_str() {
-					"random" => {
-						sim.algorithm = Box::new(RandomRouting::new());
-						do_init = true;
-					},
-					"vivaldi" => {
-						sim.algorithm = Box::new(VivaldiRouting::new());
-						do_init = true;
-					},
-					"spring" => {
-						sim.algorithm = Box::new(SpringRouting::new());
-						do_init = true;
-					},
-					"genetic" => {
-						sim.algorithm = Box::new(GeneticRouting::new());
-						do_init = true;
-					},
-					"tree" => {
-						sim.algorithm = Box::new(SpanningTreeRouting::new());
-						do_init = true;
-					}
-					_ => {
-						writeln!(out, "Unknown algorithm: {}", algo)?;
+		Command::Mobility(id, model, speed, pause) => {
+			if let Some(id) = id {
+				if sim.locations.get_position(id).is_some() {
+					sim.movements.set_model(id, model, speed, pause, &mut sim.rng);
+					writeln!(out, "mobility of node {}: {}", id, model.name())?;
+				} else {
+					writeln!(out, "node {} has no position", id)?;
+				}
+			} else {
+				sim.movements.set_model_all(&sim.locations, model, speed, pause, &mut sim.rng);
+				writeln!(out, "mobility: {}", model.name())?;
+			}
+		},
+		Command::Radio(radio) => {
+			match radio {
+				Some((range, interval)) if range > 0.0 => {
+					sim.radio = Some(RadioModel { range, interval: interval.max(1) });
+					let (added, removed) = sim.update_radio_links();
+					writeln!(out, "radio range: {}, interval: {}, links added: {}, removed: {}",
+						range, interval.max(1), added, removed)?;
+				},
+				Some(_) => {
+					sim.radio = None;
+					writeln!(out, "radio: disabled")?;
+				},
+				None => {
+					if let Some(radio) = sim.radio {
+						writeln!(out, "radio range: {}, interval: {}", radio.range, radio.interval)?;
+					} else {
+						writeln!(out, "radio: disabled")?;
 					}
 				}
-				if do_init {
+			}
+		},
+		Command::Seed(seed) => {
+			if let Some(seed) = seed {
+				sim.set_seed(seed);
+			}
+			writeln!(out, "seed: {}", sim.seed)?;
+		},
+		Command::Threads(threads) => {
+			if let Some(threads) = threads {
+				sim.test.set_threads(threads);
+			}
+			writeln!(out, "threads: {}", sim.test.threads())?;
+		},
+		Command::Record(args) => {
+			if let Some((interval, samples)) = args {
+				sim.recorder.set(interval, samples);
+			}
+			if sim.recorder.interval() > 0 {
+				writeln!(out, "record interval: {}, samples: {}, recorded: {}",
+					sim.recorder.interval(), sim.recorder.samples(), sim.recorder.series().len())?;
+			} else {
+				writeln!(out, "record: disabled, recorded: {}", sim.recorder.series().len())?;
+			}
+		},
+		Command::ExportRecord(path) => {
+			sim.recorder.export(&path)?;
+			writeln!(out, "Export done: {} ({} samples)", path, sim.recorder.series().len())?;
+		},
+		Command::Crash(id, delay, duration) => {
+			// failures start with the next simulation step
+			let step = sim.sim_steps + 1 + delay;
+			sim.failures.add(Target::Node(id), step, duration, None);
+			writeln!(out, "crash node {} at step {}", id, step)?;
+		},
+		Command::Recover(id) => {
+			sim.failures.recover(Target::Node(id), sim.sim_steps + 1);
+			writeln!(out, "recover node {} at step {}", id, sim.sim_steps + 1)?;
+		},
+		Command::Flap(from, to, period, duration) => {
+			let step = sim.sim_steps + 1;
+			sim.failures.add(Target::Link(from, to), step, duration, Some(period.max(1)));
+			writeln!(out, "flap link {} <=> {} every {} steps", from, to, period.max(1))?;
+		},
+		Command::Partition(x1, y1, x2, y2, delay, duration) => {
+			let step = sim.sim_steps + 1 + delay;
+			let area = [x1.min(x2), y1.min(y2), x1.max(x2), y1.max(y2)];
+			sim.failures.add(Target::Region(area), step, duration, None);
+			writeln!(out, "partition at step {}", step)?;
+		},
+		Command::FailuresRandom(crash_rate, flap_rate, duration) => {
+			sim.failures.set_random(crash_rate, flap_rate, duration);
+			writeln!(out, "random failures, crash rate: {}, flap rate: {}, duration: {}", crash_rate, flap_rate, duration)?;
+		},
+		Command::Failures(clear) => {
+			if clear {
+				let (added, _) = sim.clear_failures();
+				writeln!(out, "failures cleared, links restored: {}", added)?;
+			} else {
+				let (crash_rate, flap_rate, duration) = sim.failures.random();
+				writeln!(out, "failures: {}, active: {}, links removed: {}, events: {}",
+					sim.failures.scheduled(), sim.failures.active(),
+					sim.failures.removed_links(), sim.failures.log().len())?;
+				writeln!(out, "random crash rate: {}, flap rate: {}, duration: {}", crash_rate, flap_rate, duration)?;
+			}
+		},
+		Command::FailuresLog(path) => {
+			if let Some(path) = path {
+				sim.failures.export_log(&path)?;
+				writeln!(out, "Export done: {}", path)?;
+			} else {
+				for entry in sim.failures.log() {
+					writeln!(out, "{}", entry)?;
+				}
+			}
+		},
+		Command::MaxStretch(max_stretch) => {
+			if let Some(max_stretch) = max_stretch {
+				sim.test.set_max_stretch(max_stretch);
+			}
+			writeln!(out, "max stretch: {}", sim.test.max_stretch())?;
+		},
+		Command::TestDetails(detailed) => {
+			if let Some(detailed) = detailed {
+				sim.test.set_detailed(detailed);
+			}
+			writeln!(out, "test details: {}", sim.test.detailed())?;
+		},
+		Command::ExportReport(path) => {
+			sim.test.export_records(&path)?;
+			writeln!(out, "Export done: {} ({} paths)", path, sim.test.records().len())?;
+		},
+		Command::Apsp(apsp) => {
+			if let Some(apsp) = apsp {
+				sim.test.set_apsp(apsp);
+			}
+			writeln!(out, "apsp: {}", sim.test.apsp())?;
+		},
+		Command::Propagation(model) => {
+			if let Some(model) = model {
+				sim.propagation.set_model(model);
+			}
+			write!(out, "propagation: {}", sim.propagation.model().name())?;
+			match sim.propagation.model() {
+				propagation::Model::UnitDisk => {},
+				propagation::Model::LogDistance { exponent } => {
+					write!(out, ", exponent: {}", exponent)?;
+				},
+				propagation::Model::TwoRayGround { height } => {
+					write!(out, ", antenna height: {}m", height)?;
+				},
+				propagation::Model::Shadowing { exponent, sigma } => {
+					write!(out, ", exponent: {}, sigma: {}dB", exponent, sigma)?;
+				}
+			}
+			writeln!(out, ", tx power: {}dBm, sensitivity: {}dBm", sim.propagation.tx_power, sim.propagation.sensitivity)?;
+		},
+		Command::RadioPower(tx_power, sensitivity) => {
+			sim.propagation.tx_power = tx_power;
+			sim.propagation.sensitivity = sensitivity;
+			writeln!(out, "tx power: {}dBm, sensitivity: {}dBm", tx_power, sensitivity)?;
+		},
+		Command::MobilityArea(x1, y1, x2, y2) => {
+			sim.movements.set_area([x1.min(x2), y1.min(y2), x1.max(x2), y1.max(y2)]);
+		},
+		Command::Algorithm(algo) => {
+			if let Some(algo) = algo {
+				if let Some(entry) = find_algorithm(&algo) {
+					sim.algorithm = (entry.new)();
+					sim.algorithm_name = entry.name;
+					do_init = true;
 					writeln!(out, "Done")?;
+				} else {
+					writeln!(out, "Unknown algorithm: {}", algo)?;
 				}
 			} else {
 				write!(out, "selected: ")?;
 				sim.algorithm.get("name", out)?;
 				write!(out, "\n")?;
-				write!(out, "available: random, vivaldi, spring, genetic, tree\n")?;
+				write!(out, "available: ")?;
+				print_algorithms(out)?;
 			}
 		},
 		Command::Run(path) => {
@@ -750,12 +1406,53 @@ fn cmd_handler(out: &mut std::fmt::Write, sim: &mut GlobalState, input: &str, ca
 				writeln!(out, "Recursive call not allowed: {}", &path)?;
 			}
 		},
+		Command::Experiment(path, output) => {
+			let sweep = Sweep::load(&path)?;
+			for algorithm in &sweep.algorithms {
+				if find_algorithm(algorithm).is_none() {
+					return Err(MyError::new(format!("Unknown algorithm: {}", algorithm)));
+				}
+			}
+
+			let runs = sweep.runs();
+			let output = output.unwrap_or("results.csv".to_string());
+			let mut progress = Progress::new();
+			let mut results = Vec::new();
+			let now = Instant::now();
+
+			for (i, run) in runs.iter().enumerate() {
+				if sim.abort_simulation {
+					break;
+				}
+				if sim.show_progress {
+					progress.update(runs.len(), i);
+				}
+				results.push(run_experiment(&sweep, run, &sim.cmd_address)?);
+			}
+
+			export_results(&results, &output)?;
+			writeln!(out, "Run {} experiments, duration: {}, results: {}",
+				results.len(), fmt_duration(now.elapsed()), output)?;
+		},
 		Command::RemoveUnconnected => {
-			sim.graph.remove_unconnected_nodes();
+			let ids : Vec<u32> = (0..sim.graph.node_count() as u32)
+				.filter(|id| sim.graph.get_node_degree(*id) == 0).collect();
+			// remove highest id first, other ids stay valid
+			for id in ids.into_iter().rev() {
+				sim.remove_node(id);
+			}
 			do_init = true;
 		},
-		Command::RemoveNodes(ids) => {
-			sim.graph.remove_nodes(&ids);
+		Command::RemoveNodes(mut ids) => {
+			// remove highest id first, other ids stay valid
+			ids.sort_unstable();
+			ids.dedup();
+			for id in ids.into_iter().rev() {
+				if (id as usize) < sim.graph.node_count() {
+					sim.remove_node(id);
+				}
+			}
+			do_init = true;
 		},
 		Command::ConnectNodes(ids) => {
 			sim.graph.connect_nodes(&ids);
@@ -770,17 +1467,88 @@ fn cmd_handler(out: &mut std::fmt::Write, sim: &mut GlobalState, input: &str, ca
 	};
 
 	if do_init {
-		sim.algorithm.reset(sim.graph.node_count());
+		sim.algorithm.reset(sim.graph.node_count(), &mut sim.rng);
+		sim.queues.clear();
+		sim.traffic.clear();
 		sim.test.clear();
+		sim.recorder.clear();
 	}
 
-	export_file(
-		&sim.graph,
-		Some(&sim.locations),
-		Some(&*sim.algorithm),
-		mark_links.as_ref(),
-		sim.export_path.as_ref()
-	);
+	// no export in headless mode
+	if !sim.export_path.is_empty() {
+		export_file(
+			&sim.graph,
+			Some(&sim.locations),
+			Some(&sim.meta),
+			Some(&*sim.algorithm),
+			mark_links.as_ref(),
+			sim.export_path.as_ref()
+		);
+	}
 
 	Ok(())
 }
+
+// Single run of an experiment on a separate headless simulator state
+fn run_experiment(sweep: &Sweep, run: &Run, cmd_address: &str) -> Result<RunResult, MyError> {
+	let now = Instant::now();
+	let mut sim = GlobalState::new(cmd_address);
+	sim.export_path.clear();
+
+	let mut commands = vec![
+		format!("seed {}", run.rng_seed()),
+		run.topology.clone(),
+		format!("algo {}", run.algorithm),
+	];
+	commands.extend(sweep.setup.iter().cloned());
+	if run.steps > 0 {
+		commands.push(format!("sim_step {}", run.steps));
+	}
+	commands.push(format!("test {}", sweep.samples));
+
+	let mut output = String::new();
+	for command in &commands {
+		if let Command::Error(msg) = parse_command(command) {
+			return Err(MyError::new(format!("{}: {}", command, msg)));
+		}
+		cmd_handler(&mut output, &mut sim, command, AllowRecursiveCall::No)?;
+	}
+
+	Ok(RunResult {
+		run: run.clone(),
+		nodes: sim.graph.node_count(),
+		links: sim.graph.link_count(),
+		arrived: sim.test.arrived(),
+		stretch: sim.test.stretch(),
+		connectivity: sim.test.connectivity(),
+		packets: sim.traffic.total.packets,
+		bytes: sim.traffic.total.bytes,
+		duration: now.elapsed(),
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn run_commands(sim: &mut GlobalState, commands: &[&str]) {
+		let mut out = String::new();
+		for command in commands {
+			cmd_handler(&mut out, sim, command, AllowRecursiveCall::No).unwrap();
+		}
+	}
+
+	#[test]
+	fn remove_nodes_keeps_routes_valid() {
+		for algo in &["olsr", "babel"] {
+			let mut sim = GlobalState::new("127.0.0.1:8011");
+			sim.export_path.clear();
+			let algo = format!("algo {}", algo);
+			run_commands(&mut sim, &["seed 1", "gnp 100 0.05", "remove_unconnected", &algo, "sim_step 40",
+				"remove_nodes 3,10,79", "sim_step 15", "test 500"]);
+			// stale routing state would give a stretch below 1
+			assert!(sim.test.arrived() > 90.0, "{}: arrived {}", algo, sim.test.arrived());
+			assert!(sim.test.stretch() >= 1.0, "{}: stretch {}", algo, sim.test.stretch());
+		}
+	}
+}
@@ -7,6 +7,8 @@ use crate::utils::MyError;
 pub struct DebugPath {
 	costs: u32,
 	packet: TestPacket,
+	// (transmitter, receiver) of every step
+	visited: Vec<(ID, ID)>,
 	initialized: bool,
 	debug_running: bool,
 	step: u32
@@ -17,6 +19,7 @@ impl DebugPath {
 		Self {
 			costs: 0,
 			packet: TestPacket::new(0, 0, 0, 0),
+			visited: vec![],
 			initialized: false,
 			debug_running: false,
 			step: 0
@@ -26,6 +29,7 @@ impl DebugPath {
 	pub fn init(&mut self, source: u32, target: u32) {
 		self.packet = TestPacket::new(source, source, source, target);
 		self.costs = 0;
+		self.visited.clear();
 		self.initialized = true;
 		self.debug_running = true;
 		self.step = 0;
@@ -44,6 +48,18 @@ impl DebugPath {
 		self.step += 1;
 		write!(out, "step {}, path: {} => {}, cost: {}, current: {}, ", self.step, self.packet.source, self.packet.destination, self.costs, self.packet.receiver)?;
 
+		// the route only depends on the packet, a repeated state is a loop
+		let state = (self.packet.transmitter, self.packet.receiver);
+		if let Some(idx) = self.visited.iter().position(|s| *s == state) {
+			let cycle : Vec<String> = self.visited[idx..].iter()
+				.chain(std::iter::once(&state))
+				.map(|s| s.1.to_string()).collect();
+			writeln!(out, "Packet Lost - Loop detected: {}", cycle.join(" => "))?;
+			self.debug_running = false;
+			return Ok(());
+		}
+		self.visited.push(state);
+
 		if let Some(next) = route(&self.packet) {
 			// Check if link really exists
 			if let Some(link) = graph.get_link(self.packet.receiver, next) {
@@ -1,8 +1,9 @@
 use crate::graph::*;
 
-use std::collections::HashMap;
+use std::collections::{BinaryHeap, HashMap};
+use std::cmp::Ordering;
+use std::thread;
 use std::f32;
-use std::usize;
 
 
 /*
@@ -15,6 +16,59 @@ pub struct Dijkstra {
 	prevs_cache: HashMap<ID, Vec<ID>>
 }
 
+// queue entry, ordered by lowest costs first
+#[derive(Clone, Copy, PartialEq)]
+struct State {
+	costs: f32,
+	node: ID,
+}
+
+impl Eq for State {}
+
+impl Ord for State {
+	fn cmp(&self, other: &Self) -> Ordering {
+		other.costs.partial_cmp(&self.costs).unwrap_or(Ordering::Equal)
+			.then_with(|| other.node.cmp(&self.node))
+	}
+}
+
+impl PartialOrd for State {
+	fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+		Some(self.cmp(other))
+	}
+}
+
+// distances and previous nodes of all nodes from source
+fn shortest_paths(graph: &Graph, source: ID) -> (Vec<f32>, Vec<ID>) {
+	let len = graph.node_count();
+	let mut dists = vec![f32::INFINITY; len];
+	let mut prevs = vec![ID::MAX; len];
+	let mut heap = BinaryHeap::new();
+
+	dists[source as usize] = 0.0;
+	heap.push(State { costs: 0.0, node: source });
+
+	while let Some(State { costs, node }) = heap.pop() {
+		// outdated entry
+		if costs > dists[node as usize] {
+			continue;
+		}
+
+		for link in graph.get_neighbors(node) {
+			let v = link.to as usize;
+			// distance update
+			let alt = costs + link.cost() as f32;
+			if alt < dists[v] {
+				dists[v] = alt;
+				prevs[v] = node;
+				heap.push(State { costs: alt, node: link.to });
+			}
+		}
+	}
+
+	(dists, prevs)
+}
+
 impl Dijkstra {
 	pub fn new() -> Dijkstra {
 		Dijkstra {
@@ -45,68 +99,137 @@ impl Dijkstra {
 		}
 	}
 
-	fn get_shortest_path(self: &Dijkstra, source: ID, target: ID) -> Option<Vec<ID>> {
-		if let Some(prevs) = self.prevs_cache.get(&source) {
-			let mut path = vec![];
-			let mut next = target;
-			loop {
-				let prev = prevs[next as usize];
-				if prev != ID::max_value() {
-					next = prev;
-					path.push(next);
-				} else {
-					break;
-				}
-			}
-			return Some(path);
+	// Shortest path from source to target, both included.
+	// None if the target cannot be reached.
+	pub fn find_shortest_path(self: &mut Dijkstra, graph: &Graph, source: ID, target: ID) -> Option<Vec<ID>> {
+		if !self.prevs_cache.contains_key(&source) {
+			self.calculate_shortest_paths(graph, source);
+		}
+
+		let dists = self.dists_cache.get(&source)?;
+		let prevs = self.prevs_cache.get(&source)?;
+
+		if !dists[target as usize].is_finite() {
+			return None;
 		}
-		None
+
+		let mut path = vec![target];
+		let mut next = target;
+		while next != source {
+			next = prevs[next as usize];
+			path.push(next);
+		}
+		path.reverse();
+
+		Some(path)
 	}
 
 	pub fn calculate_shortest_paths(self: &mut Dijkstra, graph: &Graph, source: ID) {
+		let (dists, prevs) = shortest_paths(graph, source);
+
+		// Set cache
+		self.dists_cache.insert(source, dists);
+		self.prevs_cache.insert(source, prevs);
+	}
+}
+
+/*
+* Shortest distances between all pairs of nodes.
+* Rows are stored in one vector and calculated in parallel.
+*/
+pub struct DistanceMatrix {
+	len: usize,
+	dists: Vec<f32>,
+}
+
+impl DistanceMatrix {
+	pub fn new(graph: &Graph, threads: usize) -> Self {
 		let len = graph.node_count();
-		let mut dists = vec![f32::INFINITY; len];
-		let mut prevs = vec![ID::max_value(); len];
-		let mut q = vec![true; len];
-
-		dists[source as usize] = 0.0;
-
-		fn get_smallest(q: &Vec<bool>, dists: &Vec<f32>) -> usize {
-			let mut dist = f32::INFINITY;
-			let mut node = usize::MAX; //HM: may cause segfault
-
-			for i in 0..q.len() {
-				if q[i] {
-					let d = dists[i];
-					if d < dist {
-						node = i;
-						dist = d;
-					}
+		let mut dists = vec![f32::INFINITY; len * len];
+
+		if len > 0 {
+			// contiguous block of rows per thread
+			let rows = len.div_ceil(threads.max(1));
+
+			thread::scope(|s| {
+				for (i, block) in dists.chunks_mut(rows * len).enumerate() {
+					s.spawn(move || {
+						for (j, row) in block.chunks_mut(len).enumerate() {
+							let source = (i * rows + j) as ID;
+							row.copy_from_slice(&shortest_paths(graph, source).0);
+						}
+					});
 				}
-			}
-			node
-		};
-
-		for _ in 0..len {
-			let u = get_smallest(&q, &dists);
-			q[u] = false;
-			let links = graph.get_neighbors(u as ID);
-
-			for link in links {
-				let v = link.to as usize;
-				if q[v] {
-					// distance update
-					let alt = dists[u] + link.cost() as f32;
-					if alt < dists[v] {
-						dists[v] = alt;
-						prevs[v] = u as ID;
-					}
+			});
+		}
+
+		Self { len, dists }
+	}
+
+	pub fn distance(&self, source: ID, target: ID) -> f32 {
+		self.dists[source as usize * self.len + target as usize]
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	// 5x5 grid with directed costs and an isolated last node
+	fn test_graph() -> Graph {
+		let mut graph = Graph::new();
+		graph.add_nodes(26);
+		for y in 0..5 {
+			for x in 0..5 {
+				let id = y * 5 + x;
+				if x < 4 {
+					graph.connect(id, id + 1);
+				}
+				if y < 4 {
+					graph.connect(id, id + 5);
 				}
 			}
 		}
+		graph.set_metric(Metric::Custom);
+		for (i, link) in graph.links.clone().iter().enumerate() {
+			graph.set_link_cost(link.from, link.to, (i * 7 % 10 + 1) as u16);
+		}
+		graph
+	}
 
-		// Set cache
-		self.dists_cache.insert(source, dists);
-		self.prevs_cache.insert(source, prevs);
+	#[test]
+	fn distance_matrix_matches_dijkstra() {
+		let graph = test_graph();
+		let matrix = DistanceMatrix::new(&graph, 3);
+		let mut dijkstra = Dijkstra::new();
+
+		for source in 0..26 {
+			for target in 0..26 {
+				let expected = dijkstra.find_shortest_distance(&graph, source, target);
+				assert_eq!(matrix.distance(source, target), expected);
+			}
+		}
+		assert_eq!(matrix.distance(3, 3), 0.0);
+		assert!(!matrix.distance(0, 25).is_finite());
+	}
+
+	#[test]
+	fn shortest_path_includes_source_and_target() {
+		let graph = test_graph();
+		let mut dijkstra = Dijkstra::new();
+
+		for target in 1..25 {
+			let path = dijkstra.find_shortest_path(&graph, 0, target).unwrap();
+			assert_eq!(path.first(), Some(&0));
+			assert_eq!(path.last(), Some(&target));
+
+			let costs: u32 = path.windows(2)
+				.map(|hop| graph.get_link(hop[0], hop[1]).unwrap().cost() as u32)
+				.sum();
+			assert_eq!(costs as f32, dijkstra.find_shortest_distance(&graph, 0, target));
+		}
+
+		assert_eq!(dijkstra.find_shortest_path(&graph, 4, 4), Some(vec![4]));
+		assert_eq!(dijkstra.find_shortest_path(&graph, 0, 25), None);
 	}
 }
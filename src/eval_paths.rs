@@ -1,12 +1,154 @@
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::Write;
 use std::time::{Instant, Duration};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
 use rand::Rng;
+use rand::rngs::StdRng;
 
 use crate::progress::Progress;
-use crate::sim::TestPacket;
-use crate::dijkstra::Dijkstra;
+use crate::sim::{Io, TestPacket, RoutingAlgorithm, Forward};
+use crate::link_queues::LinkQueues;
+use crate::stats::TrafficStats;
+use crate::dijkstra::{Dijkstra, DistanceMatrix};
+use crate::utils::MyError;
 use crate::graph::*;
 
 
+fn default_threads() -> usize {
+	thread::available_parallelism().map(|n| n.get()).unwrap_or(1)
+}
+
+// How a tested path ended
+#[derive(Clone, Copy, PartialEq)]
+pub enum Outcome {
+	Arrived,
+	NoNextHop,
+	InvalidNextHop,
+	Loop,
+	// maximum path costs reached
+	TtlExceeded,
+}
+
+impl Outcome {
+	pub fn name(&self) -> &'static str {
+		match self {
+			Outcome::Arrived => "arrived",
+			Outcome::NoNextHop => "no_next_hop",
+			Outcome::InvalidNextHop => "invalid_next_hop",
+			Outcome::Loop => "loop",
+			Outcome::TtlExceeded => "ttl_exceeded",
+		}
+	}
+}
+
+// Result of a single tested path
+#[derive(Clone)]
+pub struct PathRecord {
+	pub source: ID,
+	pub target: ID,
+	// nodes the packet visited, starting with the source
+	pub path: Vec<ID>,
+	pub costs: u32,
+	pub costs_min: u32,
+	pub outcome: Outcome,
+}
+
+impl PathRecord {
+	pub fn hops(&self) -> usize {
+		self.path.len() - 1
+	}
+
+	pub fn stretch(&self) -> f32 {
+		(self.costs as f32) / (self.costs_min as f32)
+	}
+}
+
+// Results of the paths tested by one worker thread
+#[derive(Default)]
+struct Counters {
+	packets_send: u32,
+	packets_lost: u32,
+	packets_arrived: u32,
+	packets_looped: u32,
+	route_costs_sum: u64,
+	route_costs_min_sum: u64,
+	nodes_connected: usize,
+	nodes_disconnected: usize,
+	records: Vec<PathRecord>,
+	// (transmitter, receiver) of the current path, reused between paths
+	visited: HashSet<(ID, ID)>,
+	// settings of the run
+	max_stretch: u32,
+	detailed: bool,
+}
+
+fn test_path(counters: &mut Counters, graph: &Graph, route: &impl Fn(&TestPacket) -> Option<ID>,
+		source: ID, target: ID, costs_min: u32) {
+	let mut packet = TestPacket::new(source, source, source, target);
+	let mut path_costs = 0u32;
+	let mut path = vec![source];
+	let mut outcome = Outcome::TtlExceeded;
+
+	counters.packets_send += 1;
+	counters.visited.clear();
+
+	// max path costs until we give up
+	let max_costs = costs_min * counters.max_stretch;
+
+	while path_costs < max_costs {
+		// the route only depends on the packet, a repeated hop is a loop.
+		// Keep forwarding until the path costs are exceeded like before.
+		if outcome != Outcome::Loop && !counters.visited.insert((packet.transmitter, packet.receiver)) {
+			counters.packets_looped += 1;
+			outcome = Outcome::Loop;
+		}
+
+		if let Some(next) = route(&packet) {
+			// Check if link really exists
+			if let Some(link) = graph.get_link(packet.receiver, next) {
+				path_costs += link.cost() as u32;
+				path.push(next);
+				if next == packet.destination {
+					// packet arrived
+					counters.packets_arrived += 1;
+					outcome = Outcome::Arrived;
+					break;
+				} else {
+					// forward packet
+					packet.transmitter = packet.receiver;
+					packet.receiver = next;
+				}
+			} else {
+				// invalid next hop
+				counters.packets_lost += 1;
+				outcome = Outcome::InvalidNextHop;
+				break;
+			}
+		} else {
+			// no next hop
+			counters.packets_lost += 1;
+			outcome = Outcome::NoNextHop;
+			break;
+		}
+	}
+
+	counters.route_costs_sum += path_costs as u64;
+	counters.route_costs_min_sum += costs_min as u64;
+
+	if counters.detailed {
+		counters.records.push(PathRecord {
+			source,
+			target,
+			path,
+			costs: path_costs,
+			costs_min,
+			outcome,
+		});
+	}
+}
+
 /*
  * Test if all paths allow for routing.
  * This test does not allow the state of the routing algorithm to change.
@@ -17,12 +159,31 @@ pub struct EvalPaths {
 	packets_send: u32,
 	packets_lost: u32,
 	packets_arrived: u32,
-	route_costs_sum: u32,
-	route_costs_min_sum: u32,
+	// packets that revisited a hop with the same state
+	packets_looped: u32,
+	route_costs_sum: u64,
+	route_costs_min_sum: u64,
 	nodes_connected: usize,
 	nodes_disconnected: usize,
 	max_stretch: u32,
+	// route discoveries of reactive algorithms
+	discoveries: u32,
+	discovery_steps: u32,
+	discovery_packets: u64,
+	discovery_bytes: u64,
+	discovery_timeout: u32,
+	// management traffic of reactive tests, apart from the simulation traffic
+	discovery_traffic: TrafficStats,
 	run_time: Duration,
+	// worker threads for non-reactive algorithms
+	threads: usize,
+	// precompute distances of all pairs
+	apsp: bool,
+	// record every tested path
+	detailed: bool,
+	records: Vec<PathRecord>,
+	// (transmitter, receiver) of the current reactive path
+	visited: HashSet<(ID, ID)>,
 	dijkstra: Dijkstra
 }
 
@@ -34,12 +195,26 @@ impl EvalPaths {
 			packets_send: 0,
 			packets_lost: 0,
 			packets_arrived: 0,
+			packets_looped: 0,
 			route_costs_sum: 0,
 			route_costs_min_sum: 0,
 			nodes_connected: 0,
 			nodes_disconnected: 0,
 			max_stretch: 2,
+			discoveries: 0,
+			discovery_steps: 0,
+			discovery_packets: 0,
+			discovery_bytes: 0,
+			// reactive algorithms give up on their own,
+			// this only guards against endless discoveries
+			discovery_timeout: 1000,
+			discovery_traffic: TrafficStats::new(),
 			run_time: Duration::new(0, 0),
+			threads: default_threads(),
+			apsp: false,
+			detailed: false,
+			records: vec![],
+			visited: HashSet::new(),
 			dijkstra: Dijkstra::new(),
 		}
 	}
@@ -49,10 +224,17 @@ impl EvalPaths {
 		self.packets_send = 0;
 		self.packets_lost = 0;
 		self.packets_arrived = 0;
+		self.packets_looped = 0;
 		self.route_costs_sum = 0;
 		self.route_costs_min_sum = 0;
 		self.nodes_connected = 0;
 		self.nodes_disconnected = 0;
+		self.discoveries = 0;
+		self.discovery_steps = 0;
+		self.discovery_packets = 0;
+		self.discovery_bytes = 0;
+		self.discovery_traffic.clear();
+		self.records.clear();
 		self.run_time = Duration::new(0, 0);
 	}
 
@@ -62,28 +244,83 @@ impl EvalPaths {
 	}
 
 	pub fn show_progress(&mut self, show_progress: bool) {
-		self.show_progress = true;
+		self.show_progress = show_progress;
 	}
 
-	fn test_path(&mut self, graph: &Graph, mut route: impl FnMut(&TestPacket) -> Option<u32>,
-			source: ID, target: ID, costs_min: u32) {
-		// maximum stretch we record
+	// Test path with a reactive algorithm. Simulation steps are run
+	// while the algorithm waits for a route discovery.
+	fn test_path_reactive(&mut self, graph: &Graph, algorithm: &mut dyn RoutingAlgorithm,
+			queues: &mut LinkQueues, rng: &mut StdRng, source: ID, target: ID) {
+		let min = self.dijkstra.find_shortest_distance(graph, source, target);
+		if !min.is_finite() {
+			// no path from target to source => ignore
+			self.nodes_disconnected += 1;
+			return;
+		}
+		self.nodes_connected += 1;
+
+		let costs_min = min as u32;
+		let traffic = &mut self.discovery_traffic;
 		let mut packet = TestPacket::new(source, source, source, target);
 		let mut path_costs = 0u32;
+		let mut path = vec![source];
+		let mut looped = false;
+		let outcome;
 
 		self.packets_send += 1;
+		self.visited.clear();
+
+		// max path costs until we give up
+		let max_costs = costs_min * self.max_stretch;
+
+		loop {
+			let mut forward = algorithm.forward(&packet, &mut Io::new(graph, queues, traffic, rng));
 
-		// max steps to try until we give up
-		let max_steps = costs_min * self.max_stretch;
+			if forward == Forward::Buffered {
+				let packets = traffic.total.packets;
+				let bytes = traffic.total.bytes;
+				let mut steps = 0;
 
-		for _ in 0..max_steps {
-			if let Some(next) = route(&packet) {
+				// run the algorithm until the route is found, the rest of the simulation is frozen
+				while forward == Forward::Buffered && steps < self.discovery_timeout {
+					algorithm.step(&mut Io::new(graph, queues, traffic, rng));
+					queues.advance();
+					steps += 1;
+					forward = algorithm.forward(&packet, &mut Io::new(graph, queues, traffic, rng));
+				}
+
+				self.discoveries += 1;
+				self.discovery_steps += steps;
+				self.discovery_packets += traffic.total.packets - packets;
+				self.discovery_bytes += traffic.total.bytes - bytes;
+
+				// routes have changed, earlier hops do not indicate a loop
+				self.visited.clear();
+			}
+
+			// a repeated hop is a loop, keep forwarding until the path costs are exceeded
+			if !looped && !self.visited.insert((packet.transmitter, packet.receiver)) {
+				looped = true;
+			}
+
+			if let Forward::Next(next) = forward {
 				// Check if link really exists
 				if let Some(link) = graph.get_link(packet.receiver, next) {
 					path_costs += link.cost() as u32;
+					path.push(next);
 					if next == packet.destination {
 						// packet arrived
 						self.packets_arrived += 1;
+						outcome = Outcome::Arrived;
+						break;
+					} else if path_costs >= max_costs {
+						self.packets_lost += 1;
+						if looped {
+							self.packets_looped += 1;
+							outcome = Outcome::Loop;
+						} else {
+							outcome = Outcome::TtlExceeded;
+						}
 						break;
 					} else {
 						// forward packet
@@ -93,21 +330,36 @@ impl EvalPaths {
 				} else {
 					// invalid next hop
 					self.packets_lost += 1;
+					outcome = Outcome::InvalidNextHop;
 					break;
 				}
 			} else {
-				// no next hop
+				// no next hop or discovery timed out
 				self.packets_lost += 1;
+				outcome = Outcome::NoNextHop;
 				break;
 			}
 		}
 
-		self.route_costs_sum += path_costs;
-		self.route_costs_min_sum += costs_min;
+		self.route_costs_sum += path_costs as u64;
+		self.route_costs_min_sum += costs_min as u64;
+
+		if self.detailed {
+			self.records.push(PathRecord {
+				source,
+				target,
+				path,
+				costs: path_costs,
+				costs_min,
+				outcome,
+			});
+		}
 	}
 
-	pub fn run_samples(&mut self, graph: &Graph, mut route: impl FnMut(&TestPacket) -> Option<u32>,
-			samples: usize) {
+	// Like run_samples, but for reactive algorithms that may change their state.
+	// Discovery traffic is counted apart from the management traffic of the simulation.
+	pub fn run_samples_reactive(&mut self, graph: &Graph, algorithm: &mut dyn RoutingAlgorithm,
+			queues: &mut LinkQueues, rng: &mut StdRng, samples: usize) {
 		self.clear();
 
 		let node_count = graph.node_count();
@@ -124,24 +376,15 @@ impl EvalPaths {
 		}
 
 		for _ in 0..samples {
-			let source = rand::thread_rng().gen_range(0, node_count);
-			let target = rand::thread_rng().gen_range(0, node_count);
+			let source = rng.gen_range(0, node_count);
+			let target = rng.gen_range(0, node_count);
 
 			if source == target {
 				// we do not test those paths
 				continue;
 			}
 
-			let min = self.dijkstra.find_shortest_distance(graph, source as ID, target as ID);
-			if !min.is_finite() {
-				// no path from target to source => ignore
-				self.nodes_disconnected += 1;
-				continue;
-			} else {
-				self.nodes_connected += 1;
-			}
-
-			self.test_path(&graph, &mut route, source as ID, target as ID, min as u32);
+			self.test_path_reactive(graph, algorithm, queues, rng, source as ID, target as ID);
 
 			sample += 1;
 
@@ -158,7 +401,144 @@ impl EvalPaths {
 		self.is_done = true;
 	}
 
-	pub fn run_all(&mut self, graph: &Graph, mut route: impl FnMut(&TestPacket) -> Option<u32>) {
+	// Number of worker threads, 0 selects the number of cpus.
+	pub fn set_threads(&mut self, threads: usize) {
+		self.threads = if threads == 0 { default_threads() } else { threads };
+	}
+
+	pub fn threads(&self) -> usize {
+		self.threads
+	}
+
+	pub fn set_apsp(&mut self, apsp: bool) {
+		self.apsp = apsp;
+	}
+
+	pub fn apsp(&self) -> bool {
+		self.apsp
+	}
+
+	// maximum ratio of path costs to shortest path costs before a packet is dropped
+	pub fn set_max_stretch(&mut self, max_stretch: u32) {
+		self.max_stretch = max_stretch.max(1);
+	}
+
+	pub fn max_stretch(&self) -> u32 {
+		self.max_stretch
+	}
+
+	pub fn set_detailed(&mut self, detailed: bool) {
+		self.detailed = detailed;
+	}
+
+	pub fn detailed(&self) -> bool {
+		self.detailed
+	}
+
+	// tested paths of the last run in detailed mode
+	pub fn records(&self) -> &[PathRecord] {
+		&self.records
+	}
+
+	// number of recorded paths with this outcome
+	pub fn outcome_count(&self, outcome: Outcome) -> usize {
+		self.records.iter().filter(|r| r.outcome == outcome).count()
+	}
+
+	fn add_counters(&mut self, counters: Counters) {
+		self.packets_send += counters.packets_send;
+		self.packets_lost += counters.packets_lost;
+		self.packets_arrived += counters.packets_arrived;
+		self.packets_looped += counters.packets_looped;
+		self.route_costs_sum += counters.route_costs_sum;
+		self.route_costs_min_sum += counters.route_costs_min_sum;
+		self.nodes_connected += counters.nodes_connected;
+		self.nodes_disconnected += counters.nodes_disconnected;
+		self.records.extend(counters.records);
+	}
+
+	// Test paths on all worker threads. Worker i gets its pairs from pairs(i, ..).
+	// Each worker has its own Dijkstra cache, pairs of the same source are expected to be consecutive.
+	fn run_parallel<R, P>(&mut self, graph: &Graph, route: &R, tests: usize, pairs: P)
+		where R: Fn(&TestPacket) -> Option<ID> + Sync, P: Fn(usize, &mut dyn FnMut(ID, ID)) + Sync {
+		let threads = self.threads.max(1);
+		let max_stretch = self.max_stretch;
+		let detailed = self.detailed;
+		let done = AtomicUsize::new(0);
+		let mut progress = Progress::new();
+
+		if self.show_progress {
+			progress.start(tests, 0);
+		}
+
+		// distances of all pairs, computed in parallel
+		let matrix = if self.apsp {
+			Some(DistanceMatrix::new(graph, threads))
+		} else {
+			None
+		};
+		let matrix = matrix.as_ref();
+
+		let results = thread::scope(|s| {
+			let handles : Vec<_> = (0..threads).map(|worker| {
+				let pairs = &pairs;
+				let done = &done;
+				s.spawn(move || {
+					let mut counters = Counters { max_stretch, detailed, ..Counters::default() };
+					let mut dijkstra = Dijkstra::new();
+					let mut last_source = None;
+
+					pairs(worker, &mut |source, target| {
+						let min = if let Some(matrix) = matrix {
+							matrix.distance(source, target)
+						} else {
+							// only keep the distances of the current source
+							if last_source != Some(source) {
+								dijkstra.clear();
+								last_source = Some(source);
+							}
+							dijkstra.find_shortest_distance(graph, source, target)
+						};
+						if !min.is_finite() {
+							// no path from target to source => ignore
+							counters.nodes_disconnected += 1;
+						} else {
+							counters.nodes_connected += 1;
+							test_path(&mut counters, graph, route, source, target, min as u32);
+						}
+
+						done.fetch_add(1, Ordering::Relaxed);
+					});
+
+					counters
+				})
+			}).collect();
+
+			if self.show_progress {
+				while !handles.iter().all(|h| h.is_finished()) {
+					progress.update(tests, done.load(Ordering::Relaxed));
+					thread::sleep(Duration::from_millis(100));
+				}
+			}
+
+			// merge in thread order for deterministic results
+			handles.into_iter().map(|h| h.join().unwrap()).collect::<Vec<Counters>>()
+		});
+
+		for counters in results {
+			self.add_counters(counters);
+		}
+
+		// same order for any number of threads
+		self.records.sort_by_key(|r| (r.source, r.target));
+
+		if self.show_progress {
+			progress.update(tests, tests);
+		}
+	}
+
+	pub fn run_samples(&mut self, graph: &Graph, route: impl Fn(&TestPacket) -> Option<ID> + Sync,
+			samples: usize, rng: &mut StdRng) {
 		self.clear();
 
 		let node_count = graph.node_count();
@@ -167,37 +547,62 @@ impl EvalPaths {
 		}
 
 		let now = Instant::now();
-		let tests = (node_count as usize).pow(2);
-		//let mut progress = Progress::new("test: ");
-		//let mut test = 0;
-
-		//progress.start(tests, 0);
-		for source in 0..node_count {
-			for target in 0..node_count {
-				if source == target {
-					continue;
-				}
+		let threads = self.threads.max(1);
 
-				let min = self.dijkstra.find_shortest_distance(graph, source as ID, target as ID);
-				if !min.is_finite() {
-					// no path from target to source => ignore
-					self.nodes_disconnected += 1;
-					continue;
-				} else {
-					self.nodes_connected += 1;
-				}
-
-				self.test_path(&graph, &mut route, source as ID, target as ID, min as u32);
+		// draw pairs up front so that results do not depend on the number of threads
+		let mut buckets = vec![Vec::new(); threads];
+		for _ in 0..samples {
+			let source = rng.gen_range(0, node_count) as ID;
+			let target = rng.gen_range(0, node_count) as ID;
 
-				//test += 1;
-				//progress.update(tests, test);
+			if source == target {
+				// we do not test those paths
+				continue;
 			}
+
+			buckets[source as usize % threads].push((source, target));
+		}
+
+		// group by source for the Dijkstra cache
+		for bucket in &mut buckets {
+			bucket.sort_by_key(|pair| pair.0);
 		}
 
+		let tests = buckets.iter().map(|b| b.len()).sum();
+		self.run_parallel(graph, &route, tests, |worker, test| {
+			for &(source, target) in &buckets[worker] {
+				test(source, target);
+			}
+		});
+
 		self.run_time = now.elapsed();
+		self.is_done = true;
+	}
 
-		//clear progress line
-		//progress.clear_line();
+	pub fn run_all(&mut self, graph: &Graph, route: impl Fn(&TestPacket) -> Option<ID> + Sync) {
+		self.clear();
+
+		let node_count = graph.node_count();
+		if node_count < 2 {
+			return;
+		}
+
+		let now = Instant::now();
+		let threads = self.threads.max(1);
+		let tests = node_count * (node_count - 1);
+
+		self.run_parallel(graph, &route, tests, |worker, test| {
+			for source in (worker..node_count).step_by(threads) {
+				for target in 0..node_count {
+					if source != target {
+						test(source as ID, target as ID);
+					}
+				}
+			}
+		});
+
+		self.run_time = now.elapsed();
+		self.is_done = true;
 	}
 
 	pub fn duration(&self) -> Duration {
@@ -212,10 +617,44 @@ impl EvalPaths {
 		100.0 * (self.packets_arrived as f32) / (self.packets_send as f32)
 	}
 
+	pub fn loops(&self) -> u32 {
+		self.packets_looped
+	}
+
 	pub fn connectivity(&self) -> f32 {
 		100.0 * (self.nodes_connected as f32) / (self.nodes_connected + self.nodes_disconnected) as f32
 	}
 
+	pub fn discoveries(&self) -> u32 {
+		self.discoveries
+	}
+
+	// mean steps until a discovered route was available
+	pub fn discovery_latency(&self) -> f32 {
+		if self.discoveries == 0 {
+			0.0
+		} else {
+			(self.discovery_steps as f32) / (self.discoveries as f32)
+		}
+	}
+
+	// mean management packets send during a route discovery
+	pub fn discovery_overhead(&self) -> f32 {
+		if self.discoveries == 0 {
+			0.0
+		} else {
+			(self.discovery_packets as f32) / (self.discoveries as f32)
+		}
+	}
+
+	pub fn discovery_overhead_bytes(&self) -> f32 {
+		if self.discoveries == 0 {
+			0.0
+		} else {
+			(self.discovery_bytes as f32) / (self.discoveries as f32)
+		}
+	}
+
 	pub fn get_results(&self) -> Vec<(&'static str, f32)> {
 		vec![
 			("arrived", self.arrived()),
@@ -223,4 +662,140 @@ impl EvalPaths {
 			("stretch", self.stretch())
 		]
 	}
+
+	// write recorded paths as JSON or CSV, depending on the file extension
+	pub fn export_records(&self, path: &str) -> Result<(), MyError> {
+		let mut file = File::create(path)?;
+
+		let nodes = |r: &PathRecord, sep: &str| {
+			r.path.iter().map(|id| id.to_string()).collect::<Vec<String>>().join(sep)
+		};
+
+		if path.ends_with(".json") {
+			write!(file, "[")?;
+			for (i, r) in self.records.iter().enumerate() {
+				if i > 0 {
+					write!(file, ",")?;
+				}
+				write!(file, "\n{{\"source\": {}, \"target\": {}, \"outcome\": \"{}\", \"path\": [{}], ",
+					r.source, r.target, r.outcome.name(), nodes(r, ", "))?;
+				write!(file, "\"hops\": {}, \"costs\": {}, \"costs_min\": {}, \"stretch\": {}}}",
+					r.hops(), r.costs, r.costs_min, r.stretch())?;
+			}
+			writeln!(file, "\n]")?;
+		} else {
+			writeln!(file, "source,target,outcome,path,hops,costs,costs_min,stretch")?;
+			for r in &self.records {
+				writeln!(file, "{},{},{},{},{},{},{},{}",
+					r.source, r.target, r.outcome.name(), nodes(r, " "),
+					r.hops(), r.costs, r.costs_min, r.stretch())?;
+			}
+		}
+
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use rand::SeedableRng;
+
+	// line of nodes 0 - 1 - ... - count-1
+	fn line(count: u32) -> Graph {
+		let mut graph = Graph::new();
+		graph.add_nodes(count);
+		for id in 1..count {
+			graph.connect(id - 1, id);
+		}
+		graph
+	}
+
+	// next hop towards the destination on a line
+	fn line_route(packet: &TestPacket) -> Option<ID> {
+		if packet.destination > packet.receiver {
+			Some(packet.receiver + 1)
+		} else {
+			Some(packet.receiver - 1)
+		}
+	}
+
+	fn run_detailed(graph: &Graph, route: impl Fn(&TestPacket) -> Option<ID> + Sync) -> EvalPaths {
+		let mut test = EvalPaths::new();
+		test.set_threads(2);
+		test.set_detailed(true);
+		test.run_samples(graph, route, 200, &mut StdRng::seed_from_u64(0));
+		test
+	}
+
+	#[test]
+	fn records_contain_full_paths() {
+		let graph = line(6);
+		let test = run_detailed(&graph, line_route);
+
+		assert!(!test.records().is_empty());
+		for record in test.records() {
+			assert!(record.outcome == Outcome::Arrived);
+			assert_eq!(record.path.first(), Some(&record.source));
+			assert_eq!(record.path.last(), Some(&record.target));
+			assert_eq!(record.hops() as u32, record.source.abs_diff(record.target));
+			assert_eq!(record.costs, record.costs_min);
+		}
+	}
+
+	#[test]
+	fn records_end_at_the_last_reached_node() {
+		let graph = line(6);
+		// node 3 has no route
+		let test = run_detailed(&graph, |packet| if packet.receiver == 3 { None } else { line_route(packet) });
+
+		for record in test.records() {
+			let blocked = (record.source < 3 && record.target > 3) || (record.source > 3 && record.target < 3);
+			if blocked {
+				assert!(record.outcome == Outcome::NoNextHop);
+				assert_eq!(record.path.last(), Some(&3));
+			} else if record.source != 3 {
+				assert!(record.outcome == Outcome::Arrived);
+				assert_eq!(record.path.last(), Some(&record.target));
+			}
+		}
+	}
+
+	#[test]
+	fn loops_are_detected() {
+		let graph = line(4);
+		// nodes 1 and 2 send everything to each other
+		let test = run_detailed(&graph, |packet| match packet.receiver {
+			1 => Some(2),
+			2 => Some(1),
+			_ => line_route(packet),
+		});
+
+		let looped = test.records().iter().filter(|r| r.outcome == Outcome::Loop).count();
+		assert!(looped > 0);
+		assert_eq!(test.loops() as usize, looped);
+		for record in test.records() {
+			if record.outcome == Outcome::Loop {
+				assert!(record.costs >= record.costs_min * test.max_stretch());
+			}
+		}
+	}
+
+	#[test]
+	fn detours_are_not_loops() {
+		let mut graph = line(6);
+		graph.connect(5, 0);
+		// always forward clockwise around the ring
+		let clockwise = |packet: &TestPacket| Some((packet.receiver + 1) % 6);
+
+		let test = run_detailed(&graph, clockwise);
+		assert_eq!(test.loops(), 0);
+		assert!(test.records().iter().any(|r| r.outcome == Outcome::TtlExceeded));
+
+		let mut test = EvalPaths::new();
+		test.set_max_stretch(5);
+		test.run_samples(&graph, clockwise, 200, &mut StdRng::seed_from_u64(0));
+		assert_eq!(test.loops(), 0);
+		assert_eq!(test.arrived(), 100.0);
+	}
 }
@@ -0,0 +1,252 @@
+use std::fs::File;
+use std::io::Write;
+use std::time::Duration;
+use serde_json::Value;
+
+use crate::utils::{get_array, get_u64, read_file, MyError};
+
+
+/*
+ * Parameter sweep for batch experiments.
+ * Every combination of topology, algorithm, steps, seed
+ * and repetition results in a separate simulation run.
+ */
+pub struct Sweep {
+	pub algorithms: Vec<String>,
+	// expanded topology commands
+	pub topologies: Vec<String>,
+	// commands run after the topology was created
+	pub setup: Vec<String>,
+	pub steps: Vec<u32>,
+	pub seeds: Vec<u64>,
+	pub repetitions: u32,
+	pub samples: u32,
+}
+
+#[derive(Clone)]
+pub struct Run {
+	pub algorithm: String,
+	pub topology: String,
+	pub steps: u32,
+	pub seed: u64,
+	pub repetition: u32,
+}
+
+impl Run {
+	// seed of the random number generator for this run
+	pub fn rng_seed(&self) -> u64 {
+		self.seed.wrapping_add((self.repetition as u64) << 32)
+	}
+}
+
+pub struct RunResult {
+	pub run: Run,
+	pub nodes: usize,
+	pub links: usize,
+	pub arrived: f32,
+	pub stretch: f32,
+	pub connectivity: f32,
+	pub packets: u64,
+	pub bytes: u64,
+	pub duration: Duration,
+}
+
+// expand number, list of numbers or {"from", "to", "step"} range
+fn parse_values(value: &Value) -> Result<Vec<String>, MyError> {
+	fn fmt_number(n: f64) -> String {
+		if n.fract() == 0.0 {
+			format!("{}", n as i64)
+		} else {
+			format!("{}", n)
+		}
+	}
+
+	match value {
+		Value::Number(n) => {
+			Ok(vec![fmt_number(n.as_f64().unwrap_or(0.0))])
+		},
+		Value::String(s) => {
+			Ok(vec![s.clone()])
+		},
+		Value::Bool(b) => {
+			Ok(vec![b.to_string()])
+		},
+		Value::Array(values) => {
+			let mut ret = Vec::new();
+			for value in values {
+				ret.extend(parse_values(value)?);
+			}
+			Ok(ret)
+		},
+		Value::Object(_) => {
+			let from = value.get("from").and_then(Value::as_f64);
+			let to = value.get("to").and_then(Value::as_f64);
+			let step = value.get("step").and_then(Value::as_f64).unwrap_or(1.0);
+			if let (Some(from), Some(to)) = (from, to) {
+				if step <= 0.0 {
+					return Err(MyError::new("Range step must be positive".to_string()));
+				}
+				let mut ret = Vec::new();
+				let mut i = 0.0;
+				while from + i * step <= to {
+					ret.push(fmt_number(from + i * step));
+					i += 1.0;
+				}
+				Ok(ret)
+			} else {
+				Err(MyError::new("Range needs \"from\" and \"to\"".to_string()))
+			}
+		},
+		Value::Null => {
+			Err(MyError::new("Invalid parameter value".to_string()))
+		}
+	}
+}
+
+// all combinations of the arguments
+fn expand_command(command: &str, args: &[Vec<String>]) -> Vec<String> {
+	let mut ret = vec![command.to_string()];
+	for values in args {
+		let mut next = Vec::new();
+		for prefix in &ret {
+			for value in values {
+				next.push(format!("{} {}", prefix, value));
+			}
+		}
+		ret = next;
+	}
+	ret
+}
+
+fn get_strings(v: &Value, key: &str) -> Vec<String> {
+	get_array(v, key).map_or(vec![], |items| {
+		items.iter().filter_map(|item| item.as_str().map(|s| s.to_string())).collect()
+	})
+}
+
+impl Sweep {
+	pub fn load(path: &str) -> Result<Self, MyError> {
+		let data = read_file(path).ok_or(MyError::new(format!("File not found: {}", path)))?;
+		let v : Value = serde_json::from_slice(&data)?;
+
+		let algorithms = get_strings(&v, "algorithms");
+		if algorithms.is_empty() {
+			return Err(MyError::new("No algorithms in sweep".to_string()));
+		}
+
+		let mut topologies = Vec::new();
+		for topology in get_array(&v, "topologies").unwrap_or(&vec![]) {
+			if let Some(command) = topology.as_str() {
+				topologies.push(command.to_string());
+			} else if let Some(command) = topology.get("command").and_then(Value::as_str) {
+				let mut args = Vec::new();
+				for arg in get_array(topology, "args").unwrap_or(&vec![]) {
+					args.push(parse_values(arg)?);
+				}
+				topologies.extend(expand_command(command, &args));
+			} else {
+				return Err(MyError::new("Topology needs a \"command\"".to_string()));
+			}
+		}
+
+		if topologies.is_empty() {
+			return Err(MyError::new("No topologies in sweep".to_string()));
+		}
+
+		let mut steps = Vec::new();
+		if let Some(value) = v.get("steps") {
+			for step in parse_values(value)? {
+				steps.push(step.parse::<u32>().map_err(|_| MyError::new(format!("Invalid steps: {}", step)))?);
+			}
+		} else {
+			steps.push(0);
+		}
+
+		let mut seeds = Vec::new();
+		if let Some(value) = v.get("seeds") {
+			for seed in parse_values(value)? {
+				seeds.push(seed.parse::<u64>().map_err(|_| MyError::new(format!("Invalid seed: {}", seed)))?);
+			}
+		} else {
+			seeds.push(0);
+		}
+
+		Ok(Self {
+			algorithms,
+			topologies,
+			setup: get_strings(&v, "setup"),
+			steps,
+			seeds,
+			repetitions: get_u64(&v, "repetitions").unwrap_or(1) as u32,
+			samples: get_u64(&v, "samples").unwrap_or(1000) as u32,
+		})
+	}
+
+	// cartesian product of all parameters
+	pub fn runs(&self) -> Vec<Run> {
+		let mut runs = Vec::new();
+		for topology in &self.topologies {
+			for algorithm in &self.algorithms {
+				for steps in &self.steps {
+					for seed in &self.seeds {
+						for repetition in 0..self.repetitions {
+							runs.push(Run {
+								algorithm: algorithm.clone(),
+								topology: topology.clone(),
+								steps: *steps,
+								seed: *seed,
+								repetition,
+							});
+						}
+					}
+				}
+			}
+		}
+		runs
+	}
+}
+
+fn json_number(n: f32) -> String {
+	if n.is_finite() {
+		n.to_string()
+	} else {
+		"null".to_string()
+	}
+}
+
+// CSV field in quotes, quotes inside are doubled
+fn csv_field(s: &str) -> String {
+	format!("\"{}\"", s.replace('"', "\"\""))
+}
+
+// write results as JSON or CSV, depending on the file extension
+pub fn export_results(results: &[RunResult], path: &str) -> Result<(), MyError> {
+	let mut file = File::create(path)?;
+
+	if path.ends_with(".json") {
+		write!(file, "[")?;
+		for (i, r) in results.iter().enumerate() {
+			if i > 0 {
+				write!(file, ",")?;
+			}
+			write!(file, "\n{{\"algorithm\": {}, \"topology\": {}, \"steps\": {}, \"seed\": {}, \"repetition\": {}, ",
+				Value::from(r.run.algorithm.as_str()), Value::from(r.run.topology.as_str()),
+				r.run.steps, r.run.seed, r.run.repetition)?;
+			write!(file, "\"nodes\": {}, \"links\": {}, \"arrived\": {}, \"stretch\": {}, \"connectivity\": {}, ",
+				r.nodes, r.links, json_number(r.arrived), json_number(r.stretch), json_number(r.connectivity))?;
+			write!(file, "\"packets\": {}, \"bytes\": {}, \"duration_ms\": {}}}",
+				r.packets, r.bytes, r.duration.as_millis())?;
+		}
+		writeln!(file, "\n]")?;
+	} else {
+		writeln!(file, "algorithm,topology,steps,seed,repetition,nodes,links,arrived,stretch,connectivity,packets,bytes,duration_ms")?;
+		for r in results {
+			writeln!(file, "{},{},{},{},{},{},{},{},{},{},{},{},{}",
+				csv_field(&r.run.algorithm), csv_field(&r.run.topology), r.run.steps, r.run.seed, r.run.repetition,
+				r.nodes, r.links, r.arrived, r.stretch, r.connectivity,
+				r.packets, r.bytes, r.duration.as_millis())?;
+		}
+	}
+
+	Ok(())
+}
@@ -3,17 +3,22 @@ use std::fs::File;
 use std::u16;
 use std::fmt::Write;
 
+use std::collections::HashSet;
+use serde_json::{Map, Value};
+
 use crate::sim::RoutingAlgorithm;
 use crate::locations::Locations;
-use crate::graph::{Graph, ID};
+use crate::meta::Meta;
+use crate::graph::{Graph, Link, Metric, ID, ETX_UNIT};
 use crate::utils::*;
 
 
-pub fn export_file(graph: &Graph, locations: Option<&Locations>,
+pub fn export_file(graph: &Graph, locations: Option<&Locations>, meta: Option<&Meta>,
 	algo: Option<&RoutingAlgorithm>, mark_links: Option<&Graph>, path: &str) {
 	use std::io::Write;
 	if let Ok(mut file) = File::create(path) {
-		let content = export_json(&graph, locations, algo, mark_links);
+		let content = export_format(graph, locations, meta, path)
+			.unwrap_or_else(|| export_json(graph, locations, algo, mark_links));
 		file.write_all(content.as_bytes()).unwrap();
 		//println!("Wrote {}", path);
 	} else {
@@ -115,50 +120,267 @@ pub fn export_json(graph: &Graph, locations: Option<&Locations>, algo: Option<&R
 	ret
 }
 
-/*
-pub fn export_netjson(graph: &Graph) -> String {
+// metadata of a node as JSON object
+fn node_data(id: ID, meta: Option<&Meta>) -> Value {
+	meta.and_then(|meta| meta.data.get(&id))
+		.and_then(|data| serde_json::from_str::<Value>(data).ok())
+		.unwrap_or(Value::Null)
+}
+
+// meshviewer nodes have a hostname
+fn node_label(data: &Value) -> Option<&str> {
+	get_str(data, "label").or(get_str(data, "hostname"))
+}
+
+// unique node names, node ids of imported nodes are kept
+fn node_names(graph: &Graph, meta: Option<&Meta>) -> Vec<String> {
+	let mut names = Vec::with_capacity(graph.node_count());
+	let mut used = HashSet::new();
+	for id in 0..graph.node_count() as ID {
+		let data = node_data(id, meta);
+		let name = get_str(&data, "id").or(get_str(&data, "node_id"))
+			.map(|s| s.to_string())
+			.filter(|name| !used.contains(name))
+			.unwrap_or(id.to_string());
+		used.insert(name.clone());
+		names.push(name);
+	}
+	names
+}
+
+// links are undirected if both directions have the same quality and costs
+fn is_directed(graph: &Graph) -> bool {
+	graph.links.iter().any(|link| {
+		graph.get_link(link.to, link.from).is_none_or(|other| {
+			other.quality() != link.quality() || other.cost() != link.cost()
+		})
+	})
+}
+
+fn link_quality(link: &Link) -> f32 {
+	(link.quality() as f32) / (u16::MAX as f32)
+}
+
+// node id, label and addresses from metadata of imported nodes
+fn netjson_node(id: ID, name: &str, locations: Option<&Locations>, meta: Option<&Meta>) -> Value {
+	let data = node_data(id, meta);
+
+	let mut node = Map::new();
+	node.insert("id".to_string(), Value::from(name));
+
+	if let Some(label) = node_label(&data) {
+		node.insert("label".to_string(), Value::from(label));
+	}
+
+	if let Some(addresses) = data.get("local_addresses") {
+		node.insert("local_addresses".to_string(), addresses.clone());
+	}
+
+	let mut properties = data.get("properties").and_then(Value::as_object).cloned().unwrap_or(Map::new());
+	if let Some(pos) = locations.and_then(|locations| locations.get_position(id)) {
+		let mut location = Map::new();
+		location.insert("lat".to_string(), Value::from(pos[0] / DEG2KM));
+		location.insert("lng".to_string(), Value::from(pos[1] / DEG2KM));
+		properties.insert("location".to_string(), Value::Object(location));
+	}
+
+	if !properties.is_empty() {
+		node.insert("properties".to_string(), Value::Object(properties));
+	}
+
+	Value::Object(node)
+}
+
+// Export graph as NetJSON NetworkGraph.
+pub fn export_netjson(graph: &Graph, locations: Option<&Locations>, meta: Option<&Meta>) -> String {
 	let mut ret = String::new();
+	let names = node_names(graph, meta);
+	let directed = is_directed(graph);
 
-	write!(&mut ret, "{{\"type\": \"NetworkGraph\", \"protocol\": \"\", \"version\": \"\", \"metric\": \"tq\", \"directed\": false, \"multigraph\": false,").unwrap();
-	//write!(&mut ret, "\"timestamp\": \"{:?}\", ", chrono::Utc::now()).unwrap();
+	// etx costs as expected transmission count
+	let metric = graph.metric();
+	let cost = |link: &Link| -> f64 {
+		if metric == Metric::Etx {
+			link.cost() as f64 / ETX_UNIT as f64
+		} else {
+			link.cost() as f64
+		}
+	};
 
-	write!(&mut ret, "\"nodes\": [").unwrap();
-	let mut comma1 = false;
-	for (id, node) in graph.nodes.iter().enumerate() {
-		if comma1 {
+	write!(&mut ret, "{{\"type\": \"NetworkGraph\", \"protocol\": \"mesh_simulator\", \"version\": \"{}\", \"metric\": \"{}\", \"directed\": {},",
+		env!("CARGO_PKG_VERSION"), metric.name(), directed).unwrap();
+
+	write!(&mut ret, "\n\"nodes\": [").unwrap();
+	for id in 0..graph.node_count() as ID {
+		if id > 0 {
 			write!(&mut ret, ",").unwrap();
 		}
-		comma1 = true;
-		write!(&mut ret, "{{\"id\": \"{}\"}}", id).unwrap();
+		write!(&mut ret, "\n{}", netjson_node(id, &names[id as usize], locations, meta)).unwrap();
 	}
 
-	write!(&mut ret, "], \"links\": [").unwrap();
-	let mut comma2 = false;
-	for (id, node) in graph.nodes.iter().enumerate() {
-		for link in &node.links {
-			if comma2 {
-				write!(&mut ret, ",").unwrap();
-			}
-			comma2 = true;
-			if link.to < (id as ID) {
-				// how to remember
-				let source_id = id as ID;
-				let source_tq = link.quality;
-				let target_id = link.to;
-				let target_tq = if let Some(link) = graph.nodes[link.to as usize].find_link(source_id) {
-					link.quality
-				} else {
-					0.0
-				};
-				write!(&mut ret, "{{\"source\": \"{}\", \"target\": \"{}\", \"source_tq\": {}, \"target_tq\": {}}}",
-					source_id, target_id, source_tq, target_tq
-				).unwrap();
-			}
+	write!(&mut ret, "],\n\"links\": [").unwrap();
+	let mut comma = false;
+	for link in &graph.links {
+		if !directed && link.from > link.to {
+			continue;
 		}
+
+		if comma {
+			write!(&mut ret, ",").unwrap();
+		}
+		comma = true;
+
+		write!(&mut ret, "\n{{\"source\": {}, \"target\": {}, \"cost\": {}, \"properties\": {{\"quality\": {}}}}}",
+			Value::from(names[link.from as usize].as_str()), Value::from(names[link.to as usize].as_str()),
+			cost(link), link_quality(link)).unwrap();
 	}
 
-	write!(&mut ret, "]}}").unwrap();
+	write!(&mut ret, "\n]}}\n").unwrap();
+
+	ret
+}
+
+fn xml_escape(s: &str) -> String {
+	s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+		.replace('"', "&quot;").replace('\'', "&apos;")
+}
+
+fn dot_escape(s: &str) -> String {
+	s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/*
+ * Export graph as GraphML for Gephi and networkx.
+ * Nodes have a label, the position (in km) and
+ * the metadata as JSON string, links the quality.
+ */
+pub fn export_graphml(graph: &Graph, locations: Option<&Locations>, meta: Option<&Meta>) -> String {
+	let mut ret = String::new();
+	let names = node_names(graph, meta);
+	let directed = is_directed(graph);
+
+	writeln!(&mut ret, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>").unwrap();
+	writeln!(&mut ret, "<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">").unwrap();
+	writeln!(&mut ret, "  <key id=\"label\" for=\"node\" attr.name=\"label\" attr.type=\"string\"/>").unwrap();
+	writeln!(&mut ret, "  <key id=\"x\" for=\"node\" attr.name=\"x\" attr.type=\"double\"/>").unwrap();
+	writeln!(&mut ret, "  <key id=\"y\" for=\"node\" attr.name=\"y\" attr.type=\"double\"/>").unwrap();
+	writeln!(&mut ret, "  <key id=\"meta\" for=\"node\" attr.name=\"meta\" attr.type=\"string\"/>").unwrap();
+	writeln!(&mut ret, "  <key id=\"quality\" for=\"edge\" attr.name=\"quality\" attr.type=\"double\"/>").unwrap();
+	writeln!(&mut ret, "  <graph id=\"G\" edgedefault=\"{}\">", if directed { "directed" } else { "undirected" }).unwrap();
+
+	for id in 0..graph.node_count() as ID {
+		write!(&mut ret, "    <node id=\"{}\">", xml_escape(&names[id as usize])).unwrap();
+		if let Some(label) = node_label(&node_data(id, meta)) {
+			write!(&mut ret, "<data key=\"label\">{}</data>", xml_escape(label)).unwrap();
+		}
+		if let Some(pos) = locations.and_then(|locations| locations.get_position(id)) {
+			write!(&mut ret, "<data key=\"x\">{}</data><data key=\"y\">{}</data>", pos[0], pos[1]).unwrap();
+		}
+		if let Some(data) = meta.and_then(|meta| meta.data.get(&id)) {
+			write!(&mut ret, "<data key=\"meta\">{}</data>", xml_escape(data)).unwrap();
+		}
+		writeln!(&mut ret, "</node>").unwrap();
+	}
+
+	for link in &graph.links {
+		if !directed && link.from > link.to {
+			continue;
+		}
+		writeln!(&mut ret, "    <edge source=\"{}\" target=\"{}\"><data key=\"quality\">{}</data></edge>",
+			xml_escape(&names[link.from as usize]), xml_escape(&names[link.to as usize]), link_quality(link)).unwrap();
+	}
+
+	writeln!(&mut ret, "  </graph>\n</graphml>").unwrap();
 
 	ret
 }
-*/
+
+/*
+ * Export graph as GML. As networkx expects, the node
+ * label is the node name, the label of imported nodes
+ * is part of the metadata.
+ */
+pub fn export_gml(graph: &Graph, locations: Option<&Locations>, meta: Option<&Meta>) -> String {
+	let mut ret = String::new();
+	let names = node_names(graph, meta);
+	let directed = is_directed(graph);
+
+	writeln!(&mut ret, "graph [\n  directed {}", if directed { 1 } else { 0 }).unwrap();
+
+	for id in 0..graph.node_count() as ID {
+		writeln!(&mut ret, "  node [\n    id {}\n    label \"{}\"", id, xml_escape(&names[id as usize])).unwrap();
+		if let Some(pos) = locations.and_then(|locations| locations.get_position(id)) {
+			writeln!(&mut ret, "    graphics [\n      x {}\n      y {}\n    ]", pos[0], pos[1]).unwrap();
+		}
+		if let Some(data) = meta.and_then(|meta| meta.data.get(&id)) {
+			writeln!(&mut ret, "    meta \"{}\"", xml_escape(data)).unwrap();
+		}
+		writeln!(&mut ret, "  ]").unwrap();
+	}
+
+	for link in &graph.links {
+		if !directed && link.from > link.to {
+			continue;
+		}
+		writeln!(&mut ret, "  edge [\n    source {}\n    target {}\n    quality {}\n  ]",
+			link.from, link.to, link_quality(link)).unwrap();
+	}
+
+	writeln!(&mut ret, "]").unwrap();
+
+	ret
+}
+
+// Export graph as Graphviz DOT, positions (in km) are fixed.
+pub fn export_dot(graph: &Graph, locations: Option<&Locations>, meta: Option<&Meta>) -> String {
+	let mut ret = String::new();
+	let names = node_names(graph, meta);
+	let directed = is_directed(graph);
+
+	writeln!(&mut ret, "{} G {{", if directed { "digraph" } else { "graph" }).unwrap();
+
+	for id in 0..graph.node_count() as ID {
+		let mut attrs = vec![];
+		if let Some(label) = node_label(&node_data(id, meta)) {
+			attrs.push(format!("label=\"{}\"", dot_escape(label)));
+		}
+		if let Some(pos) = locations.and_then(|locations| locations.get_position(id)) {
+			attrs.push(format!("pos=\"{},{}!\"", pos[0], pos[1]));
+		}
+		if let Some(data) = meta.and_then(|meta| meta.data.get(&id)) {
+			attrs.push(format!("meta=\"{}\"", dot_escape(data)));
+		}
+
+		write!(&mut ret, "  \"{}\"", dot_escape(&names[id as usize])).unwrap();
+		if !attrs.is_empty() {
+			write!(&mut ret, " [{}]", attrs.join(", ")).unwrap();
+		}
+		writeln!(&mut ret, ";").unwrap();
+	}
+
+	for link in &graph.links {
+		if !directed && link.from > link.to {
+			continue;
+		}
+		writeln!(&mut ret, "  \"{}\" {} \"{}\" [quality={}];",
+			dot_escape(&names[link.from as usize]), if directed { "->" } else { "--" },
+			dot_escape(&names[link.to as usize]), link_quality(link)).unwrap();
+	}
+
+	writeln!(&mut ret, "}}").unwrap();
+
+	ret
+}
+
+// GraphML, GML or DOT by file extension, None for other files
+pub fn export_format(graph: &Graph, locations: Option<&Locations>, meta: Option<&Meta>, path: &str) -> Option<String> {
+	if path.ends_with(".graphml") {
+		Some(export_graphml(graph, locations, meta))
+	} else if path.ends_with(".gml") {
+		Some(export_gml(graph, locations, meta))
+	} else if path.ends_with(".dot") || path.ends_with(".gv") {
+		Some(export_dot(graph, locations, meta))
+	} else {
+		None
+	}
+}
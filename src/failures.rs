@@ -0,0 +1,256 @@
+use std::collections::BTreeSet;
+use std::fs::File;
+use std::io::Write;
+use rand::Rng;
+use rand::rngs::StdRng;
+
+use crate::graph::{Graph, Link, ID};
+use crate::locations::Locations;
+use crate::movements::Area;
+use crate::utils::MyError;
+
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Target {
+	// all links of a node
+	Node(ID),
+	// both directions of a link
+	Link(ID, ID),
+	// links between nodes inside and outside of an area
+	Region(Area),
+}
+
+impl Target {
+	fn describe(&self) -> String {
+		match self {
+			Target::Node(id) => format!("node {}", id),
+			Target::Link(from, to) => format!("link {} <=> {}", from, to),
+			Target::Region(a) => format!("partition ({}, {}) - ({}, {})", a[0], a[1], a[2], a[3]),
+		}
+	}
+}
+
+#[derive(Clone)]
+struct Failure {
+	target: Target,
+	// first step of the failure
+	start: u32,
+	// step of recovery, None for never
+	end: Option<u32>,
+	// alternate between down and up every period steps
+	period: Option<u32>,
+	// failure was applied in the last step
+	active: bool,
+}
+
+impl Failure {
+	fn is_active(&self, step: u32) -> bool {
+		step >= self.start
+			&& self.end.is_none_or(|end| step < end)
+			&& self.period.is_none_or(|period| ((step - self.start) / period).is_multiple_of(2))
+	}
+}
+
+/*
+ * Inject node crashes, link flaps and partitions during the simulation.
+ * Failures are scheduled for a step or happen at random.
+ * Links removed by a failure are put back on recovery.
+ */
+pub struct Failures {
+	failures: Vec<Failure>,
+	// probability per step that a node crashes
+	crash_rate: f32,
+	// probability per step that a link goes down
+	flap_rate: f32,
+	// steps a random failure lasts
+	random_duration: u32,
+	// links removed by active failures
+	removed: Vec<Link>,
+	log: Vec<String>,
+}
+
+impl Failures {
+	pub fn new() -> Self {
+		Self {
+			failures: vec![],
+			crash_rate: 0.0,
+			flap_rate: 0.0,
+			random_duration: 10,
+			removed: vec![],
+			log: vec![],
+		}
+	}
+
+	pub fn clear(&mut self) {
+		self.failures.clear();
+		self.crash_rate = 0.0;
+		self.flap_rate = 0.0;
+		self.removed.clear();
+		self.log.clear();
+	}
+
+	// schedule a failure at step for duration steps (0 for no recovery)
+	pub fn add(&mut self, target: Target, step: u32, duration: u32, period: Option<u32>) {
+		self.failures.push(Failure {
+			target,
+			start: step,
+			end: if duration == 0 { None } else { Some(step + duration) },
+			period: period.filter(|p| *p > 0),
+			active: false,
+		});
+	}
+
+	// end failures of target with the next step
+	pub fn recover(&mut self, target: Target, step: u32) {
+		for failure in &mut self.failures {
+			if failure.target == target && failure.end.is_none_or(|end| end > step) {
+				failure.end = Some(step.max(failure.start));
+			}
+		}
+	}
+
+	pub fn set_random(&mut self, crash_rate: f32, flap_rate: f32, duration: u32) {
+		self.crash_rate = crash_rate;
+		self.flap_rate = flap_rate;
+		self.random_duration = duration.max(1);
+	}
+
+	pub fn random(&self) -> (f32, f32, u32) {
+		(self.crash_rate, self.flap_rate, self.random_duration)
+	}
+
+	pub fn log(&self) -> &[String] {
+		&self.log
+	}
+
+	pub fn scheduled(&self) -> usize {
+		self.failures.len()
+	}
+
+	pub fn active(&self) -> usize {
+		self.failures.iter().filter(|f| f.active).count()
+	}
+
+	pub fn removed_links(&self) -> usize {
+		self.removed.len()
+	}
+
+	// Update failures for this step. Returns true if the set of active failures changed.
+	pub fn update(&mut self, step: u32, graph: &Graph, rng: &mut StdRng) -> bool {
+		// random failures skip nodes and links that already failed
+		let mut nodes = BTreeSet::new();
+		let mut pairs = BTreeSet::new();
+		for failure in self.failures.iter().filter(|f| f.is_active(step)) {
+			match failure.target {
+				Target::Node(id) => { nodes.insert(id); },
+				Target::Link(from, to) => { pairs.insert((from.min(to), from.max(to))); },
+				Target::Region(_) => {},
+			}
+		}
+
+		if self.crash_rate > 0.0 {
+			for id in 0..graph.node_count() as ID {
+				if rng.gen::<f32>() < self.crash_rate && !nodes.contains(&id) {
+					self.add(Target::Node(id), step, self.random_duration, None);
+				}
+			}
+		}
+
+		if self.flap_rate > 0.0 {
+			for link in &graph.links {
+				if link.from < link.to && rng.gen::<f32>() < self.flap_rate
+						&& !pairs.contains(&(link.from, link.to)) {
+					self.add(Target::Link(link.from, link.to), step, self.random_duration, None);
+				}
+			}
+		}
+
+		let mut changed = false;
+		for failure in &mut self.failures {
+			let active = failure.is_active(step);
+			if active != failure.active {
+				failure.active = active;
+				changed = true;
+				let what = if active {
+					"failed"
+				} else {
+					"recovered"
+				};
+				self.log.push(format!("step {}: {} {}", step, failure.target.describe(), what));
+			}
+		}
+
+		// forget failures that are over
+		self.failures.retain(|f| f.active || f.end.is_none_or(|end| step < end));
+
+		changed
+	}
+
+	// drop failures and removed links of a node, higher node ids move down by one
+	pub fn remove_node(&mut self, id: ID) {
+		let shift = |k: ID| if k > id { k - 1 } else { k };
+
+		self.failures.retain(|f| match f.target {
+			Target::Node(n) => n != id,
+			Target::Link(from, to) => from != id && to != id,
+			Target::Region(_) => true,
+		});
+
+		for failure in &mut self.failures {
+			failure.target = match failure.target {
+				Target::Node(n) => Target::Node(shift(n)),
+				Target::Link(from, to) => Target::Link(shift(from), shift(to)),
+				target => target,
+			};
+		}
+
+		self.removed.retain(|l| l.from != id && l.to != id);
+		for link in &mut self.removed {
+			link.from = shift(link.from);
+			link.to = shift(link.to);
+		}
+	}
+
+	// links removed by failures, to be passed to filter_links again
+	pub fn take_removed(&mut self) -> Vec<Link> {
+		std::mem::take(&mut self.removed)
+	}
+
+	// Remove and remember links affected by active failures.
+	pub fn filter_links(&mut self, links: Vec<Link>, locations: &Locations) -> Vec<Link> {
+		let mut nodes = BTreeSet::new();
+		let mut pairs = BTreeSet::new();
+		let mut regions = vec![];
+
+		for failure in self.failures.iter().filter(|f| f.active) {
+			match failure.target {
+				Target::Node(id) => { nodes.insert(id); },
+				Target::Link(from, to) => { pairs.insert((from.min(to), from.max(to))); },
+				Target::Region(area) => { regions.push(area); },
+			}
+		}
+
+		let inside = |id: ID, area: &Area| {
+			locations.data.get(&id).is_some_and(|p| {
+				p[0] >= area[0] && p[0] <= area[2] && p[1] >= area[1] && p[1] <= area[3]
+			})
+		};
+
+		let (removed, kept) : (Vec<Link>, Vec<Link>) = links.into_iter().partition(|l| {
+			nodes.contains(&l.from) || nodes.contains(&l.to)
+				|| pairs.contains(&(l.from.min(l.to), l.from.max(l.to)))
+				|| regions.iter().any(|area| inside(l.from, area) != inside(l.to, area))
+		});
+
+		self.removed.extend(removed);
+		kept
+	}
+
+	pub fn export_log(&self, path: &str) -> Result<(), MyError> {
+		let mut file = File::create(path)?;
+		for entry in &self.log {
+			writeln!(file, "{}", entry)?;
+		}
+		Ok(())
+	}
+}
@@ -12,30 +12,71 @@ use crate::utils::vec_filter;
 
 pub type ID = u32;
 
+// link cost of a perfect link for the ETX and ETT metrics
+pub const ETX_UNIT : f32 = 256.0;
+
+// How link costs are derived
+#[derive(Clone, Copy, PartialEq)]
+pub enum Metric {
+	// every link costs 1
+	Hop,
+	// expected transmission count from the quality of both directions
+	Etx,
+	// expected transmission time, ETX divided by bandwidth
+	Ett,
+	// cost set for each link
+	Custom,
+}
+
+impl std::str::FromStr for Metric {
+	type Err = ();
+
+	fn from_str(name: &str) -> Result<Self, Self::Err> {
+		match name {
+			"hop" => Ok(Metric::Hop),
+			"etx" => Ok(Metric::Etx),
+			"ett" => Ok(Metric::Ett),
+			"custom" => Ok(Metric::Custom),
+			_ => Err(())
+		}
+	}
+}
+
+impl Metric {
+	pub fn name(&self) -> &'static str {
+		match self {
+			Metric::Hop => "hop",
+			Metric::Etx => "etx",
+			Metric::Ett => "ett",
+			Metric::Custom => "custom",
+		}
+	}
+}
+
 #[derive(Clone, PartialEq)]
 pub struct Link
 {
 	pub from: ID,
 	pub to: ID,
 	pub quality: u16,
-	//bandwidth: u16,
+	// in Mbit/s
+	bandwidth: u16,
 	//channel: u8
 	cost: u16,
+	// cost for the custom metric
+	custom_cost: u16,
 }
 
 impl Link {
 	pub fn new(from: ID, to: ID, quality: u16) -> Self {
-		Self {from, to, quality, cost: 1}
+		Self {from, to, quality, bandwidth: 1, cost: 1, custom_cost: 1}
 	}
 
+	// cost according to the metric of the graph
 	pub fn cost(&self) -> u16 {
 		self.cost
 	}
 
-	pub fn bandwidth(&self) -> u16 {
-		1
-	}
-
 	pub fn quality(&self) -> u16 {
 		self.quality
 	}
@@ -60,6 +101,7 @@ impl fmt::Debug for Link {
 pub struct Graph {
 	pub links: Vec<Link>, // sorted link list
 	pub node_count: usize,
+	metric: Metric,
 }
 
 impl Graph {
@@ -67,6 +109,7 @@ impl Graph {
 		Self {
 			links: vec![],
 			node_count: 0,
+			metric: Metric::Hop,
 		}
 	}
 
@@ -97,6 +140,70 @@ impl Graph {
 		}
 
 		self.links.sort_unstable_by(|a, b| a.cmp(b.from, b.to));
+		self.update_costs();
+	}
+
+	pub fn metric(&self) -> Metric {
+		self.metric
+	}
+
+	pub fn set_metric(&mut self, metric: Metric) {
+		self.metric = metric;
+		self.update_costs();
+	}
+
+	fn calculate_cost(&self, link: &Link) -> u16 {
+		let etx = || {
+			// assume a symmetric link if the reverse direction is unknown
+			let reverse = self.link_idx(link.to, link.from)
+				.map_or(link.quality, |idx| self.links[idx].quality);
+			let df = link.quality as f32 / u16::MAX as f32;
+			let dr = reverse as f32 / u16::MAX as f32;
+			(ETX_UNIT / (df * dr)).min(u16::MAX as f32)
+		};
+
+		match self.metric {
+			Metric::Hop => 1,
+			Metric::Etx => etx() as u16,
+			Metric::Ett => (etx() / link.bandwidth.max(1) as f32).max(1.0) as u16,
+			Metric::Custom => link.custom_cost,
+		}
+	}
+
+	// recalculate the costs of all links
+	fn update_costs(&mut self) {
+		for idx in 0..self.links.len() {
+			self.links[idx].cost = self.calculate_cost(&self.links[idx]);
+		}
+	}
+
+	// recalculate the costs of a link and its reverse direction
+	fn update_link_costs(&mut self, from: ID, to: ID) {
+		for &(a, b) in &[(from, to), (to, from)] {
+			if let Some(idx) = self.link_idx(a, b) {
+				self.links[idx].cost = self.calculate_cost(&self.links[idx]);
+			}
+		}
+	}
+
+	pub fn set_link_cost(&mut self, from: ID, to: ID, cost: u16) -> bool {
+		if let Some(idx) = self.link_idx(from, to) {
+			self.links[idx].custom_cost = cost.max(1);
+			self.update_link_costs(from, to);
+			true
+		} else {
+			false
+		}
+	}
+
+	pub fn set_link_bandwidth(&mut self, from: ID, to: ID, bandwidth: u16) -> bool {
+		if let Some(idx) = self.link_idx(from, to) {
+			self.links[idx].bandwidth = bandwidth.max(1);
+			self.update_link_costs(from, to);
+			true
+		} else {
+			false
+		}
 	}
 
 	pub fn disconnect_nodes(&mut self, ids: &Vec<ID>) {
@@ -325,6 +432,10 @@ impl Graph {
 			}
 			true
 		});
+
+		for s in links.chunks(2) {
+			self.update_link_costs(s[0], s[1]);
+		}
 	}
 
 	pub fn is_bidirectional(&self) -> bool {
@@ -361,6 +472,9 @@ impl Graph {
 
 		self.node_count -= 1;
 
+		// remove links
+		vec_filter(&mut self.links, |link| link.from != id && link.to != id);
+
 		// adjust index
 		for link in &mut self.links {
 			if link.to > id {
@@ -371,9 +485,6 @@ impl Graph {
 			}
 		}
 
-		// remove links
-		vec_filter(&mut self.links, |ref link| link.from != id && link.to != id);
-
 		// sort
 		self.links.sort_unstable_by(|a, b| a.cmp(b.from, b.to));
 	}
@@ -413,6 +524,7 @@ impl Graph {
 					self.links.insert(idx, Link::new(from, to, tq));
 				}
 			}
+			self.update_link_costs(from, to);
 		}
 	}
 
@@ -439,6 +551,25 @@ impl Graph {
 		}
 	}
 
+	// replace all links
+	pub fn set_links(&mut self, mut links: Vec<Link>) {
+		// the last of duplicate links is kept
+		links.reverse();
+		links.sort_by(|a, b| a.cmp(b.from, b.to));
+		links.dedup_by(|a, b| a.from == b.from && a.to == b.to);
+
+		// keep bandwidth and custom cost of existing links
+		for link in &mut links {
+			if let Some(idx) = self.link_idx(link.from, link.to) {
+				link.bandwidth = self.links[idx].bandwidth;
+				link.custom_cost = self.links[idx].custom_cost;
+			}
+		}
+
+		self.links = links;
+		self.update_costs();
+	}
+
 	pub fn clear_links(&mut self) {
 		self.links.clear();
 	}
@@ -475,7 +606,9 @@ impl Graph {
 	}
 
 	pub fn spanning_tree(&self) -> Graph {
-		Self::minimum_spanning_tree_impl(&self.links, self.node_count)
+		let mut graph = Self::minimum_spanning_tree_impl(&self.links, self.node_count);
+		graph.metric = self.metric;
+		graph
 	}
 
 	pub fn minimum_spanning_tree(&self) -> Graph {
@@ -486,7 +619,9 @@ impl Graph {
 			links
 		};
 
-		Self::minimum_spanning_tree_impl(&links, self.node_count)
+		let mut graph = Self::minimum_spanning_tree_impl(&links, self.node_count);
+		graph.metric = self.metric;
+		graph
 	}
 
 	// Implementation of the Kruskal minimum spanning tree algorithm
@@ -525,6 +660,6 @@ impl Graph {
 			|acc, (i, j)| acc + ((i as u32) == *j) as usize
 		);
 
-		Graph{ node_count: (mst.len() + roots_count), links: mst }
+		Graph{ node_count: (mst.len() + roots_count), links: mst, metric: Metric::Hop }
 	}
 }
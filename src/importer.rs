@@ -1,57 +1,108 @@
 
 use std::fs::File;
 use std::io::Read;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::borrow::BorrowMut;
 
 use serde_json::Value;
 use crate::meta::Meta;
 use crate::locations::Locations;
-use crate::graph::{Graph, ID};
+use crate::graph::{Graph, Link, Metric, ID, ETX_UNIT};
 use crate::utils::*;
 
 
-pub fn import_file(graph: &mut Graph, loc: Option<&mut Locations>, meta: Option<&mut Meta>, path: &str) -> Result<(), MyError> {
+fn read_string(path: &str) -> Result<String, MyError> {
 	let mut file = File::open(path)?;
 	let mut data = String::new();
 	file.read_to_string(&mut data)?;
-	parse_netjson(graph, loc, meta, &data)
+	Ok(data)
 }
 
-fn extract_location(node: &Value) -> (f32, f32) {
-	if let (Some(lat), Some(lon)) = (
-		node.pointer("location/latitude").and_then(Value::as_f64),
-		node.pointer("location/longitude").and_then(Value::as_f64)) {
-			(lat as f32, lon as f32)
+fn is_edge_list(path: &str) -> bool {
+	[".tsv", ".txt", ".edges", ".edgelist"].iter().any(|ext| path.ends_with(ext))
+}
+
+// Import graph, the positions file is only supported for edge lists.
+pub fn import_file(graph: &mut Graph, loc: Option<&mut Locations>, meta: Option<&mut Meta>, path: &str, positions: Option<&str>) -> Result<(), MyError> {
+	let data = read_string(path)?;
+
+	if positions.is_some() && !is_edge_list(path) {
+		return Err(MyError::new("Positions file is only supported for edge lists".to_string()));
+	}
+
+	// format by file extension
+	if is_edge_list(path) {
+		let positions = match positions {
+			Some(positions) => Some(read_string(positions)?),
+			None => None,
+		};
+		add_parsed_graph(graph, loc, meta, parse_tsv(&data, positions.as_deref())?)
+	} else if path.ends_with(".graphml") {
+		add_parsed_graph(graph, loc, meta, parse_graphml(&data)?)
+	} else if path.ends_with(".gml") {
+		add_parsed_graph(graph, loc, meta, parse_gml(&data)?)
+	} else if path.ends_with(".dot") || path.ends_with(".gv") {
+		add_parsed_graph(graph, loc, meta, parse_dot(&data)?)
 	} else {
-		(0.0, 0.0)
+		parse_netjson(graph, loc, meta, &data)
 	}
 }
 
-// parse the meshviewer data
+// position in km from meshviewer or netjsongraph.js style locations (in degrees)
+fn extract_location(node: &Value) -> Option<[f32; 3]> {
+	let lat_lon = if let (Some(lat), Some(lon)) = (
+		node.pointer("/location/latitude").and_then(Value::as_f64),
+		node.pointer("/location/longitude").and_then(Value::as_f64)) {
+			Some((lat, lon))
+	} else if let (Some(lat), Some(lon)) = (
+		node.pointer("/properties/location/lat").and_then(Value::as_f64),
+		node.pointer("/properties/location/lng").and_then(Value::as_f64)) {
+			Some((lat, lon))
+	} else {
+		None
+	};
+
+	lat_lon.map(|(lat, lon)| [lat as f32 * DEG2KM, lon as f32 * DEG2KM, 0.0])
+}
+
+// link quality from 0.0 to 1.0
+fn to_quality(tq: f64) -> u16 {
+	(tq.clamp(0.0, 1.0) * u16::MAX as f64).round() as u16
+}
+
+/*
+ * Parse NetJSON NetworkGraph and meshviewer data.
+ * Node objects are stored as metadata (label, local_addresses, properties).
+ * Link costs are used as custom metric.
+ */
 fn parse_netjson(graph: &mut Graph, mut loc: Option<&mut Locations>, mut meta: Option<&mut Meta>, data: &str) -> Result<(), MyError> {
 	let v = serde_json::from_str::<Value>(data)?;
 
+	if let Some(kind) = get_str(&v, "type") {
+		if kind != "NetworkGraph" {
+			return Err(MyError::new(format!("Unsupported NetJSON type: {}", kind)));
+		}
+	}
+
 	if let (Some(nodes), Some(links)) = (get_array(&v, "nodes"), get_array(&v, "links")) {
 		// map target/source field to node id in graph.nodes
 		let mut map = HashMap::<&str, usize>::new();
 		let mut id = graph.node_count();
 
 		for node in nodes {
-			// try different keys (netjson uses node_id)
-			for key in &["node_id", "id"] {
+			// try different keys (netjson uses id, meshviewer node_id)
+			for key in &["id", "node_id"] {
 				if let Some(node_id) = get_str(&node, key) {
-					let (x, y) = extract_location(node);
-					let meta_data = serde_json::to_string(&node).unwrap_or(String::new());
-					// TODO: move to Node::new() ctor
-					//state.graph.add_node_with_meta(x, y, 0.0, meta);
-					//add("".to_string(), [x, y, 0.0]);
-
-					if let Some(loc) = loc.borrow_mut() {
-						loc.data.insert(id as ID, [x, y, 0.0]);
+					if map.contains_key(node_id) {
+						break;
+					}
+
+					if let (Some(loc), Some(pos)) = (loc.borrow_mut(), extract_location(node)) {
+						loc.data.insert(id as ID, pos);
 					}
 
 					if let Some(meta) = meta.borrow_mut() {
+						let meta_data = serde_json::to_string(&node).unwrap_or(String::new());
 						meta.data.insert(id as ID, meta_data);
 					}
 
@@ -65,43 +116,905 @@ fn parse_netjson(graph: &mut Graph, mut loc: Option<&mut Locations>, mut meta: O
 
 		graph.add_nodes(map.len() as u32);
 
+		let directed = v.get("directed").and_then(Value::as_bool).unwrap_or(false);
+
+		// ETX costs are scaled like the etx metric, other costs are rounded
+		let file_metric = get_str(&v, "metric").unwrap_or("");
+		let scale = if file_metric.eq_ignore_ascii_case("etx") { ETX_UNIT as f64 } else { 1.0 };
+		let mut costs = vec![];
+
 		for link in links {
-			if let (Some(source), Some(source_tq), Some(target), Some(target_tq)) =
-					(get_str(link, "source"), get_f64(link, "source_tq"),
-					get_str(link, "target"), get_f64(link, "target_tq")) {
-				if let (Some(source_id), Some(target_id)) = (map.get(source), map.get(target)) {
-					graph.add_link(*source_id as ID, *target_id as ID, (source_tq * std::u16::MAX as f64) as u16);
-					graph.add_link(*target_id as ID, *source_id as ID, (target_tq * std::u16::MAX as f64) as u16);
+			let (source_id, target_id) = match (get_str(link, "source"), get_str(link, "target")) {
+				(Some(source), Some(target)) => match (map.get(source), map.get(target)) {
+					(Some(source_id), Some(target_id)) => (*source_id as ID, *target_id as ID),
+					_ => continue,
+				},
+				_ => continue,
+			};
+
+			if let (Some(source_tq), Some(target_tq)) = (get_f64(link, "source_tq"), get_f64(link, "target_tq")) {
+				// meshviewer link with the quality of both directions
+				graph.add_link(source_id, target_id, to_quality(source_tq));
+				graph.add_link(target_id, source_id, to_quality(target_tq));
+			} else {
+				let quality = link.pointer("/properties/quality").and_then(Value::as_f64).unwrap_or(1.0);
+				graph.add_link(source_id, target_id, to_quality(quality));
+				if !directed {
+					graph.add_link(target_id, source_id, to_quality(quality));
+				}
+			}
+
+			if let Some(cost) = get_f64(link, "cost") {
+				let cost = (cost * scale).round().max(1.0).min(u16::MAX as f64) as u16;
+				costs.push((source_id, target_id, cost));
+				if !directed {
+					costs.push((target_id, source_id, cost));
 				}
 			}
 		}
+
+		// keep the current metric if the costs are in its units
+		let metric = graph.metric();
+		let same_units = file_metric.eq_ignore_ascii_case("hop")
+			|| file_metric.eq_ignore_ascii_case(metric.name())
+			|| costs.iter().all(|&(from, to, cost)| graph.get_link(from, to).is_none_or(|link| link.cost() == cost));
+
+		if !same_units && metric != Metric::Custom {
+			// links from before the import keep their costs
+			let old_count = (id - map.len()) as ID;
+			let old_costs : Vec<(ID, ID, u16)> = graph.links.iter()
+				.filter(|link| link.from < old_count)
+				.map(|link| (link.from, link.to, link.cost())).collect();
+			for (from, to, cost) in old_costs {
+				graph.set_link_cost(from, to, cost);
+			}
+			graph.set_metric(Metric::Custom);
+		}
+
+		for (from, to, cost) in costs {
+			graph.set_link_cost(from, to, cost);
+		}
+
 		Ok(())
 	} else {
 		Err(MyError::new("nodes/links fields missing".to_string()))
 	}
 }
 
+// node or link attributes as read from the file
+type Attributes = Vec<(String, String)>;
+
+// graph as read from GraphML, GML, DOT files or edge lists
+struct ParsedGraph {
+	directed: bool,
+	nodes: Vec<(String, Attributes)>,
+	links: Vec<(String, String, Attributes)>,
+}
+
+impl ParsedGraph {
+	fn new() -> Self {
+		Self { directed: false, nodes: vec![], links: vec![] }
+	}
+}
+
+fn get_attr<'a>(attrs: &'a Attributes, key: &str) -> Option<&'a str> {
+	attrs.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str())
+}
+
 /*
-fn parse_tsv(graph: &mut Graph, data: &str) {
-    macro_rules! g {
-        ($x:expr) => (get_from::<f64>(&mut $x));
-    }
-    if let Ok(file) = File::open("foo.txt") {
-        for line in std::io::BufReader::new(file).lines() {
-            if let Ok(line) = line { 
-                if !line.starts_with('#') {
-                    let mut it = line.split_whitespace();
-                    if let (Some(id), Some(other_id), tq) = (g!(it), g!(it), g!(it)) {
-                        println!("link {} {} {}", id, other_id, tq.unwrap_or(0.0));
-                    }
-                }
-            } else {
-                break;
-            }
-        }
-        Some(graph)
-    } else {
-        None
-    }
-   // Ok(graph)
-}*/
+ * Add nodes and links of a parsed file to the graph.
+ * Positions are taken from x/y (in km), the meta attribute
+ * is used as metadata. Other attributes are kept as metadata.
+ */
+fn add_parsed_graph(graph: &mut Graph, mut loc: Option<&mut Locations>, mut meta: Option<&mut Meta>, parsed: ParsedGraph) -> Result<(), MyError> {
+	let mut map = HashMap::<String, ID>::new();
+	let mut id = graph.node_count() as ID;
+
+	// nodes might only appear in links
+	let mut nodes = parsed.nodes;
+	for (source, target, _) in &parsed.links {
+		nodes.push((source.clone(), vec![]));
+		nodes.push((target.clone(), vec![]));
+	}
+
+	for (name, attrs) in &nodes {
+		if map.contains_key(name) {
+			continue;
+		}
+
+		if let Some(loc) = loc.borrow_mut() {
+			let coord = |key| get_attr(attrs, key).and_then(|v| v.trim().parse::<f32>().ok());
+			if let (Some(x), Some(y)) = (coord("x"), coord("y")) {
+				loc.data.insert(id, [x, y, coord("z").unwrap_or(0.0)]);
+			}
+		}
+
+		if let Some(meta) = meta.borrow_mut() {
+			let data = get_attr(attrs, "meta").filter(|data| serde_json::from_str::<Value>(data).is_ok());
+			if let Some(data) = data {
+				meta.data.insert(id, data.to_string());
+			} else {
+				let mut obj = serde_json::Map::new();
+				obj.insert("id".to_string(), Value::from(name.as_str()));
+				for (key, value) in attrs {
+					if !["x", "y", "z", "meta"].contains(&key.as_str()) {
+						obj.insert(key.clone(), Value::from(value.as_str()));
+					}
+				}
+				meta.data.insert(id, Value::Object(obj).to_string());
+			}
+		}
+
+		map.insert(name.clone(), id);
+		id += 1;
+	}
+
+	graph.add_nodes(map.len() as u32);
+
+	// set all links at once, adding them one by one is slow for large graphs.
+	// Later links replace earlier links between the same nodes.
+	let mut links = graph.links.clone();
+	for (source, target, attrs) in &parsed.links {
+		let quality = to_quality(get_attr(attrs, "quality").and_then(|q| q.trim().parse::<f64>().ok()).unwrap_or(1.0));
+		let (source_id, target_id) = (map[source], map[target]);
+		if source_id != target_id {
+			links.push(Link::new(source_id, target_id, quality));
+			if !parsed.directed {
+				links.push(Link::new(target_id, source_id, quality));
+			}
+		}
+	}
+	graph.set_links(links);
+
+	Ok(())
+}
+
+fn xml_unescape(s: &str) -> String {
+	s.replace("&lt;", "<").replace("&gt;", ">").replace("&quot;", "\"")
+		.replace("&apos;", "'").replace("&amp;", "&")
+}
+
+// value of an attribute in a XML tag
+fn xml_attr(tag: &str, name: &str) -> Option<String> {
+	let mut rest = tag;
+	while let Some(pos) = rest.find(name) {
+		let before = rest[..pos].chars().last();
+		let after = rest[pos + name.len()..].trim_start();
+		rest = &rest[pos + name.len()..];
+		if before.is_some_and(char::is_whitespace) && after.starts_with('=') {
+			let value = after[1..].trim_start();
+			if let Some(quote) = value.chars().next().filter(|c| *c == '"' || *c == '\'') {
+				if let Some(end) = value[1..].find(quote) {
+					return Some(xml_unescape(&value[1..end + 1]));
+				}
+			}
+		}
+	}
+	None
+}
+
+fn parse_graphml(data: &str) -> Result<ParsedGraph, MyError> {
+	let mut parsed = ParsedGraph::new();
+	// key id => attribute name
+	let mut keys = HashMap::<String, String>::new();
+	// current node or link and data key
+	let mut node : Option<(String, Attributes)> = None;
+	let mut link : Option<(String, String, Attributes)> = None;
+	let mut key : Option<String> = None;
+	let mut rest = data;
+
+	while let Some(start) = rest.find('<') {
+		let text = &rest[..start];
+		rest = &rest[start..];
+
+		// skip comments
+		if rest.starts_with("<!--") {
+			let end = rest.find("-->").ok_or(MyError::new("Unterminated XML comment".to_string()))?;
+			rest = &rest[end + 3..];
+			continue;
+		}
+
+		let end = rest.find('>').ok_or(MyError::new("Unterminated XML tag".to_string()))?;
+		let tag = rest[1..end].trim();
+		rest = &rest[end + 1..];
+
+		let closing = tag.starts_with('/');
+		let empty = tag.ends_with('/');
+		let name = tag.trim_start_matches('/').split(|c: char| c.is_whitespace() || c == '/').next().unwrap_or("");
+
+		match (name, closing) {
+			("key", false) => {
+				if let (Some(id), Some(attr)) = (xml_attr(tag, "id"), xml_attr(tag, "attr.name")) {
+					keys.insert(id, attr);
+				}
+			},
+			("graph", false) => {
+				parsed.directed = xml_attr(tag, "edgedefault").is_none_or(|d| d == "directed");
+			},
+			("node", false) => {
+				let id = xml_attr(tag, "id").ok_or(MyError::new("GraphML node without id".to_string()))?;
+				if empty {
+					parsed.nodes.push((id, vec![]));
+				} else {
+					node = Some((id, vec![]));
+				}
+			},
+			("node", true) => {
+				parsed.nodes.extend(node.take());
+			},
+			("edge", false) => {
+				if let (Some(source), Some(target)) = (xml_attr(tag, "source"), xml_attr(tag, "target")) {
+					if empty {
+						parsed.links.push((source, target, vec![]));
+					} else {
+						link = Some((source, target, vec![]));
+					}
+				}
+			},
+			("edge", true) => {
+				parsed.links.extend(link.take());
+			},
+			("data", false) => {
+				key = xml_attr(tag, "key");
+			},
+			("data", true) => {
+				if let Some(k) = key.take() {
+					let attr = (keys.get(&k).cloned().unwrap_or(k), xml_unescape(text));
+					if let Some(ref mut node) = node {
+						node.1.push(attr);
+					} else if let Some(ref mut link) = link {
+						link.2.push(attr);
+					}
+				}
+			},
+			_ => {}
+		}
+	}
+
+	Ok(parsed)
+}
+
+// GML value, either a string or number or a list of key value pairs
+enum GmlValue {
+	Value(String),
+	List(Vec<(String, GmlValue)>),
+}
+
+fn gml_tokens(data: &str) -> Result<Vec<String>, MyError> {
+	let mut tokens = vec![];
+	let mut chars = data.chars().peekable();
+
+	while let Some(c) = chars.next() {
+		if c.is_whitespace() {
+			continue;
+		} else if c == '#' {
+			// comment until end of line
+			for c in chars.by_ref() {
+				if c == '\n' {
+					break;
+				}
+			}
+		} else if c == '"' {
+			let mut s = String::new();
+			loop {
+				match chars.next() {
+					Some('"') => break,
+					Some(c) => s.push(c),
+					None => return Err(MyError::new("Unterminated GML string".to_string())),
+				}
+			}
+			// mark as string to keep an empty string a token
+			tokens.push(format!("\"{}", xml_unescape(&s)));
+		} else if c == '[' || c == ']' {
+			tokens.push(c.to_string());
+		} else {
+			let mut s = c.to_string();
+			while let Some(&c) = chars.peek() {
+				if c.is_whitespace() || c == '[' || c == ']' || c == '"' {
+					break;
+				}
+				s.push(c);
+				chars.next();
+			}
+			tokens.push(s);
+		}
+	}
+
+	Ok(tokens)
+}
+
+fn gml_list(tokens: &[String], pos: &mut usize) -> Result<Vec<(String, GmlValue)>, MyError> {
+	let mut list = vec![];
+	while *pos < tokens.len() && tokens[*pos] != "]" {
+		let key = tokens[*pos].clone();
+		*pos += 1;
+		let value = match tokens.get(*pos).map(|s| s.as_str()) {
+			Some("[") => {
+				*pos += 1;
+				let list = gml_list(tokens, pos)?;
+				if tokens.get(*pos).map(|s| s.as_str()) != Some("]") {
+					return Err(MyError::new("Missing ] in GML file".to_string()));
+				}
+				GmlValue::List(list)
+			},
+			Some(value) => GmlValue::Value(value.trim_start_matches('"').to_string()),
+			None => return Err(MyError::new(format!("Missing value of {} in GML file", key))),
+		};
+		*pos += 1;
+		list.push((key, value));
+	}
+	Ok(list)
+}
+
+fn parse_gml(data: &str) -> Result<ParsedGraph, MyError> {
+	let tokens = gml_tokens(data)?;
+	let mut pos = 0;
+	let top = gml_list(&tokens, &mut pos)?;
+
+	let items = top.iter().find_map(|(key, value)| match value {
+		GmlValue::List(items) if key == "graph" => Some(items),
+		_ => None,
+	}).ok_or(MyError::new("No graph in GML file".to_string()))?;
+
+	// scalar values of a list, graphics of nodes are flattened
+	fn attributes(items: &[(String, GmlValue)]) -> Attributes {
+		let mut attrs = vec![];
+		for (key, value) in items {
+			match value {
+				GmlValue::Value(v) => attrs.push((key.clone(), v.clone())),
+				GmlValue::List(list) if key == "graphics" => attrs.extend(attributes(list)),
+				_ => {}
+			}
+		}
+		attrs
+	}
+
+	let mut parsed = ParsedGraph::new();
+	// node id => node name, the label is the name like in networkx
+	let mut names = HashMap::<String, String>::new();
+	for (key, value) in items {
+		match (key.as_str(), value) {
+			("directed", GmlValue::Value(v)) => {
+				parsed.directed = v == "1";
+			},
+			("node", GmlValue::List(list)) => {
+				let mut attrs = attributes(list);
+				let id = get_attr(&attrs, "id").ok_or(MyError::new("GML node without id".to_string()))?.to_string();
+				let name = get_attr(&attrs, "label").unwrap_or(&id).to_string();
+				attrs.retain(|(k, _)| k != "id" && k != "label");
+				names.insert(id, name.clone());
+				parsed.nodes.push((name, attrs));
+			},
+			("edge", GmlValue::List(list)) => {
+				let attrs = attributes(list);
+				if let (Some(source), Some(target)) = (get_attr(&attrs, "source"), get_attr(&attrs, "target")) {
+					let name = |id: &str| names.get(id).cloned().unwrap_or(id.to_string());
+					parsed.links.push((name(source), name(target), attrs.clone()));
+				}
+			},
+			_ => {}
+		}
+	}
+
+	Ok(parsed)
+}
+
+fn dot_tokens(data: &str) -> Result<Vec<String>, MyError> {
+	let mut tokens = vec![];
+	let mut chars = data.chars().peekable();
+
+	while let Some(c) = chars.next() {
+		if c.is_whitespace() {
+			continue;
+		} else if c == '#' || (c == '/' && chars.peek() == Some(&'/')) {
+			// comment until end of line
+			for c in chars.by_ref() {
+				if c == '\n' {
+					break;
+				}
+			}
+		} else if c == '/' && chars.peek() == Some(&'*') {
+			chars.next();
+			let mut last = ' ';
+			for c in chars.by_ref() {
+				if last == '*' && c == '/' {
+					break;
+				}
+				last = c;
+			}
+		} else if c == '"' {
+			let mut s = String::new();
+			loop {
+				match chars.next() {
+					Some('\\') => {
+						match chars.next() {
+							Some('"') => s.push('"'),
+							Some('\\') => s.push('\\'),
+							Some('\n') => {},
+							Some(c) => { s.push('\\'); s.push(c); },
+							None => {},
+						}
+					},
+					Some('"') => break,
+					Some(c) => s.push(c),
+					None => return Err(MyError::new("Unterminated DOT string".to_string())),
+				}
+			}
+			tokens.push(format!("\"{}", s));
+		} else if c == '-' && (chars.peek() == Some(&'>') || chars.peek() == Some(&'-')) {
+			tokens.push(format!("-{}", chars.next().unwrap()));
+		} else if "{}[]=;,:".contains(c) {
+			tokens.push(c.to_string());
+		} else {
+			let mut s = c.to_string();
+			while let Some(&c) = chars.peek() {
+				if c.is_whitespace() || "{}[]=;,:\"".contains(c) || (c == '-' && !s.chars().all(|c| c.is_ascii_digit() || c == '.')) {
+					break;
+				}
+				s.push(c);
+				chars.next();
+			}
+			tokens.push(s);
+		}
+	}
+
+	Ok(tokens)
+}
+
+// Graphviz DOT: nodes, edges (also chains) and attribute lists, subgraphs are flattened
+fn parse_dot(data: &str) -> Result<ParsedGraph, MyError> {
+	let tokens = dot_tokens(data)?;
+	let mut parsed = ParsedGraph::new();
+	let is_id = |t: &str| t.starts_with('"') || (!"{}[]=;,:".contains(t) && t != "->" && t != "--");
+	let name = |t: &str| t.trim_start_matches('"').to_string();
+
+	let is_edge = |pos: usize| tokens.get(pos).is_some_and(|t| t == "->" || t == "--");
+
+	// node ids of a group {a b c} and the position after it, None for subgraphs with statements
+	let node_group = |mut pos: usize| {
+		let mut ids = vec![];
+		pos += 1;
+		while pos < tokens.len() {
+			let token = tokens[pos].as_str();
+			if token == "}" {
+				return Some((ids, pos + 1));
+			} else if token == ";" || token == "," {
+				pos += 1;
+			} else if is_id(token) && !is_edge(pos + 1) && tokens.get(pos + 1).is_none_or(|t| t != "=" && t != "[") {
+				ids.push(name(token));
+				pos += 1;
+			} else {
+				return None;
+			}
+		}
+		None
+	};
+
+	let mut pos = tokens.iter().position(|t| t == "{").ok_or(MyError::new("No graph in DOT file".to_string()))?;
+	parsed.directed = tokens[..pos].iter().any(|t| t == "digraph");
+	pos += 1;
+
+	while pos < tokens.len() {
+		let token = tokens[pos].as_str();
+
+		// node group at the start of an edge chain, e.g. {a b} -> c
+		let group = if token == "{" { node_group(pos) } else { None };
+		let first = match group {
+			Some((ids, next)) if is_edge(next) => {
+				pos = next;
+				ids
+			},
+			_ => {
+				if !is_id(token) {
+					pos += 1;
+					continue;
+				}
+
+				// skip subgraph and its name
+				if token == "subgraph" {
+					pos += 1;
+					if tokens.get(pos).is_some_and(|t| is_id(t)) {
+						pos += 1;
+					}
+					continue;
+				}
+
+				pos += 1;
+				vec![name(token)]
+			}
+		};
+
+		// node ids of a chain a -> {b c} -> d, ports are ignored
+		let mut chain = vec![first];
+		while pos < tokens.len() {
+			if tokens[pos] == ":" {
+				pos += 2;
+			} else if is_edge(pos) && pos + 1 < tokens.len() {
+				if tokens[pos + 1] == "subgraph" {
+					return Err(MyError::new("Unsupported subgraph as edge target in DOT file".to_string()));
+				} else if tokens[pos + 1] == "{" {
+					let (ids, next) = node_group(pos + 1)
+						.ok_or(MyError::new("Unsupported subgraph as edge target in DOT file".to_string()))?;
+					chain.push(ids);
+					pos = next;
+				} else {
+					chain.push(vec![name(&tokens[pos + 1])]);
+					pos += 2;
+				}
+			} else {
+				break;
+			}
+		}
+
+		// graph attribute
+		if tokens.get(pos).map(|t| t.as_str()) == Some("=") {
+			pos += 2;
+			continue;
+		}
+
+		let mut attrs = vec![];
+		while tokens.get(pos).map(|t| t.as_str()) == Some("[") {
+			pos += 1;
+			while pos < tokens.len() && tokens[pos] != "]" {
+				if tokens.get(pos + 1).map(|t| t.as_str()) == Some("=") && pos + 2 < tokens.len() {
+					attrs.push((name(&tokens[pos]), name(&tokens[pos + 2])));
+					pos += 3;
+				} else {
+					pos += 1;
+				}
+			}
+			pos += 1;
+		}
+
+		if chain.len() == 1 {
+			let mut ids = chain.remove(0);
+			// attribute defaults are ignored
+			if !["node", "edge", "graph"].contains(&ids[0].as_str()) || token.starts_with('"') {
+				// Graphviz positions are "x,y" or "x,y!"
+				if let Some(p) = get_attr(&attrs, "pos").map(|p| p.trim_end_matches('!').to_string()) {
+					let mut xy = p.split(',');
+					if let (Some(x), Some(y)) = (xy.next(), xy.next()) {
+						attrs.push(("x".to_string(), x.to_string()));
+						attrs.push(("y".to_string(), y.to_string()));
+					}
+				}
+				attrs.retain(|(k, _)| k != "pos");
+				parsed.nodes.push((ids.remove(0), attrs));
+			}
+		} else {
+			for pair in chain.windows(2) {
+				for from in &pair[0] {
+					for to in &pair[1] {
+						parsed.links.push((from.clone(), to.clone(), attrs.clone()));
+					}
+				}
+			}
+		}
+	}
+
+	Ok(parsed)
+}
+
+// fields of a line without comment, separated by whitespace or comma
+fn tsv_fields(line: &str) -> Vec<&str> {
+	line.split('#').next().unwrap_or("")
+		.split(|c: char| c.is_whitespace() || c == ',')
+		.filter(|f| !f.is_empty())
+		.collect()
+}
+
+/*
+ * Parse an edge list with "<from> <to> [<quality>]" per line.
+ * Links are added in both directions unless the file
+ * lists the reverse direction as well.
+ * Positions are lines of "<node> <x> <y> [<z>]" (in km).
+ */
+fn parse_tsv(data: &str, positions: Option<&str>) -> Result<ParsedGraph, MyError> {
+	let mut parsed = ParsedGraph::new();
+	parsed.directed = true;
+
+	let mut links = vec![];
+	for (i, line) in data.lines().enumerate() {
+		let fields = tsv_fields(line);
+		match fields.len() {
+			0 => {},
+			2 | 3 => {
+				let quality = match fields.get(2) {
+					Some(q) => q.parse::<f64>().map_err(|_| MyError::new(format!("Invalid quality in line {}: {}", i + 1, q)))?,
+					None => 1.0,
+				};
+				links.push((fields[0].to_string(), fields[1].to_string(), quality));
+			},
+			_ => {
+				return Err(MyError::new(format!("Invalid link in line {}: {}", i + 1, line)));
+			}
+		}
+	}
+
+	// node ids follow the order of appearance in the links
+	let mut index = HashMap::<String, usize>::new();
+	for (from, to, _) in &links {
+		for name in &[from, to] {
+			if !index.contains_key(*name) {
+				index.insert(name.to_string(), parsed.nodes.len());
+				parsed.nodes.push((name.to_string(), vec![]));
+			}
+		}
+	}
+
+	let listed : HashSet<(&str, &str)> = links.iter().map(|(a, b, _)| (a.as_str(), b.as_str())).collect();
+	for (from, to, quality) in &links {
+		let attrs = vec![("quality".to_string(), quality.to_string())];
+		parsed.links.push((from.clone(), to.clone(), attrs.clone()));
+		if !listed.contains(&(to.as_str(), from.as_str())) {
+			parsed.links.push((to.clone(), from.clone(), attrs));
+		}
+	}
+
+	if let Some(positions) = positions {
+		for (i, line) in positions.lines().enumerate() {
+			let fields = tsv_fields(line);
+			match fields.len() {
+				0 => {},
+				3 | 4 => {
+					let mut attrs = vec![];
+					for (key, value) in ["x", "y", "z"].iter().zip(&fields[1..]) {
+						value.parse::<f32>().map_err(|_| MyError::new(format!("Invalid position in line {}: {}", i + 1, value)))?;
+						attrs.push((key.to_string(), value.to_string()));
+					}
+					if let Some(&idx) = index.get(fields[0]) {
+						parsed.nodes[idx].1 = attrs;
+					} else {
+						// nodes without links come last
+						index.insert(fields[0].to_string(), parsed.nodes.len());
+						parsed.nodes.push((fields[0].to_string(), attrs));
+					}
+				},
+				_ => {
+					return Err(MyError::new(format!("Invalid position in line {}: {}", i + 1, line)));
+				}
+			}
+		}
+	}
+
+	Ok(parsed)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::exporter::*;
+
+	fn import(parsed: ParsedGraph) -> Graph {
+		let mut graph = Graph::new();
+		add_parsed_graph(&mut graph, None, None, parsed).unwrap();
+		graph
+	}
+
+	// four nodes with positions, asymmetric qualities and custom costs
+	fn test_graph() -> (Graph, Locations) {
+		let mut graph = Graph::new();
+		let mut locations = Locations::new();
+		graph.add_nodes(4);
+		for id in 0..4 {
+			locations.data.insert(id, [id as f32 * 0.5, 1.0 - id as f32 * 0.25, 0.0]);
+		}
+		graph.add_link(0, 1, to_quality(1.0));
+		graph.add_link(1, 0, to_quality(0.5));
+		graph.add_link(1, 2, to_quality(0.75));
+		graph.add_link(2, 3, to_quality(1.0));
+		graph.add_link(3, 2, to_quality(1.0));
+		graph.set_metric(Metric::Custom);
+		graph.set_link_cost(0, 1, 3);
+		graph.set_link_cost(1, 2, 7);
+		graph.set_link_cost(2, 3, 2);
+		graph.set_link_cost(3, 2, 2);
+		(graph, locations)
+	}
+
+	// same links with the same quality and costs
+	fn assert_same_links(a: &Graph, b: &Graph, costs: bool) {
+		assert_eq!(a.node_count(), b.node_count());
+		assert_eq!(a.link_count(), b.link_count());
+		for link in &a.links {
+			let other = b.get_link(link.from, link.to).unwrap();
+			assert_eq!(other.quality(), link.quality());
+			if costs {
+				assert_eq!(other.cost(), link.cost());
+			}
+		}
+	}
+
+	fn assert_same_positions(a: &Locations, b: &Locations) {
+		assert_eq!(a.data.len(), b.data.len());
+		for (id, pos) in &a.data {
+			let other = b.get_position(*id).unwrap();
+			assert!((pos[0] - other[0]).abs() < 1e-3 && (pos[1] - other[1]).abs() < 1e-3);
+		}
+	}
+
+	#[test]
+	fn netjson_round_trip() {
+		let (graph, locations) = test_graph();
+		let data = export_netjson(&graph, Some(&locations), None);
+
+		let mut imported = Graph::new();
+		let mut imported_locations = Locations::new();
+		parse_netjson(&mut imported, Some(&mut imported_locations), None, &data).unwrap();
+
+		assert!(imported.metric() == Metric::Custom);
+		assert_same_links(&graph, &imported, true);
+		assert_same_positions(&locations, &imported_locations);
+	}
+
+	#[test]
+	fn netjson_keeps_metric_for_hop_costs() {
+		let data = r#"{"type": "NetworkGraph", "metric": "hop", "nodes": [{"id": "a"}, {"id": "b"}],
+			"links": [{"source": "a", "target": "b", "cost": 1}]}"#;
+
+		let mut graph = Graph::new();
+		graph.set_metric(Metric::Etx);
+		parse_netjson(&mut graph, None, None, data).unwrap();
+		assert!(graph.metric() == Metric::Etx);
+		assert_eq!(graph.get_link(0, 1).unwrap().cost(), ETX_UNIT as u16);
+	}
+
+	#[test]
+	fn netjson_scales_etx_costs() {
+		let data = r#"{"type": "NetworkGraph", "metric": "etx", "nodes": [{"id": "a"}, {"id": "b"}],
+			"links": [{"source": "a", "target": "b", "cost": 2.5}]}"#;
+
+		let mut graph = Graph::new();
+		parse_netjson(&mut graph, None, None, data).unwrap();
+		assert!(graph.metric() == Metric::Custom);
+		assert_eq!(graph.link_count(), 2);
+		assert_eq!(graph.get_link(1, 0).unwrap().cost(), (2.5 * ETX_UNIT) as u16);
+	}
+
+	#[test]
+	fn graphml_parser() {
+		let parsed = parse_graphml(r#"<?xml version="1.0" encoding="UTF-8"?>
+			<graphml xmlns="http://graphml.graphdrawing.org/xmlns">
+			<!-- <node id="x"/> -->
+			<key id="d0" for="node" attr.name="label" attr.type="string"/>
+			<key id="d1" for="edge" attr.name="quality" attr.type="double"/>
+			<graph id="G" edgedefault="undirected">
+				<node id="a"><data key="d0">A &amp; B</data></node>
+				<node id="b"/>
+				<edge source="a" target="b"><data key="d1">0.5</data></edge>
+				<edge source="b" target="c"/>
+			</graph>
+			</graphml>"#).unwrap();
+
+		assert!(!parsed.directed);
+		assert_eq!(parsed.nodes.len(), 2);
+		assert_eq!(get_attr(&parsed.nodes[0].1, "label"), Some("A & B"));
+		assert_eq!(parsed.links.len(), 2);
+
+		let graph = import(parsed);
+		assert_eq!(graph.node_count(), 3);
+		assert_eq!(graph.link_count(), 4);
+		assert_eq!(graph.get_link(1, 0).unwrap().quality(), to_quality(0.5));
+		assert_eq!(graph.get_link(2, 1).unwrap().quality(), to_quality(1.0));
+	}
+
+	#[test]
+	fn gml_parser() {
+		let parsed = parse_gml(r#"graph [
+			directed 1
+			node [ id 0 label "a" graphics [ x 1.5 y -2 ] ]
+			node [ id 1 label "b" ]
+			edge [ source 0 target 1 quality 0.25 ]
+		]"#).unwrap();
+
+		assert!(parsed.directed);
+		assert_eq!(parsed.nodes[0].0, "a");
+		assert_eq!(parsed.links[0].0, "a");
+		assert_eq!(parsed.links[0].1, "b");
+
+		let mut graph = Graph::new();
+		let mut locations = Locations::new();
+		add_parsed_graph(&mut graph, Some(&mut locations), None, parsed).unwrap();
+		assert_eq!(graph.link_count(), 1);
+		assert_eq!(graph.get_link(0, 1).unwrap().quality(), to_quality(0.25));
+		assert_eq!(locations.get_position(0), Some(&[1.5, -2.0, 0.0]));
+		assert_eq!(locations.get_position(1), None);
+	}
+
+	#[test]
+	fn dot_parser() {
+		let parsed = parse_dot(r#"digraph G {
+			rankdir=LR; // comment
+			node [shape=box];
+			/* a -> x */
+			"a" [pos="1,2!"];
+			a -> {b c} -> d [quality=0.5];
+		}"#).unwrap();
+
+		assert!(parsed.directed);
+		assert_eq!(parsed.nodes.len(), 1);
+		let links: Vec<(&str, &str)> = parsed.links.iter().map(|(a, b, _)| (a.as_str(), b.as_str())).collect();
+		assert_eq!(links, vec![("a", "b"), ("a", "c"), ("b", "d"), ("c", "d")]);
+
+		let mut graph = Graph::new();
+		let mut locations = Locations::new();
+		add_parsed_graph(&mut graph, Some(&mut locations), None, parsed).unwrap();
+		assert_eq!(graph.node_count(), 4);
+		assert!(graph.links.iter().all(|link| link.quality() == to_quality(0.5)));
+		assert_eq!(locations.get_position(0), Some(&[1.0, 2.0, 0.0]));
+
+		assert!(parse_dot("digraph { a -> subgraph s { b } }").is_err());
+	}
+
+	#[test]
+	fn graph_formats_round_trip() {
+		let (graph, locations) = test_graph();
+		type Export = fn(&Graph, Option<&Locations>, Option<&Meta>) -> String;
+		type Parse = fn(&str) -> Result<ParsedGraph, MyError>;
+		let formats : [(Export, Parse); 3] = [
+			(export_graphml, parse_graphml),
+			(export_gml, parse_gml),
+			(export_dot, parse_dot),
+		];
+
+		// symmetric links are written as undirected graph
+		let mut symmetric = Graph::new();
+		symmetric.add_nodes(3);
+		symmetric.connect(0, 1);
+		symmetric.connect(1, 2);
+
+		for (export, parse) in formats.iter() {
+			let parsed = parse(&export(&graph, Some(&locations), None)).unwrap();
+			assert!(parsed.directed);
+
+			let mut imported = Graph::new();
+			let mut imported_locations = Locations::new();
+			add_parsed_graph(&mut imported, Some(&mut imported_locations), None, parsed).unwrap();
+			// costs are not stored
+			assert_same_links(&graph, &imported, false);
+			assert_same_positions(&locations, &imported_locations);
+
+			let parsed = parse(&export(&symmetric, None, None)).unwrap();
+			assert!(!parsed.directed);
+			assert_same_links(&symmetric, &import(parsed), false);
+		}
+	}
+
+	#[test]
+	fn tsv_parser() {
+		let edges = "# comment\n\nb a 0.5\na,c\nc\tb # quality 1\nb c 0.25\n";
+		let positions = "a 1 2\nc 3 4 5\nd 6 7\n";
+		let parsed = parse_tsv(edges, Some(positions)).unwrap();
+
+		// numbered by appearance, the unlinked node comes last
+		let names: Vec<&str> = parsed.nodes.iter().map(|(name, _)| name.as_str()).collect();
+		assert_eq!(names, vec!["b", "a", "c", "d"]);
+
+		let mut graph = Graph::new();
+		let mut locations = Locations::new();
+		add_parsed_graph(&mut graph, Some(&mut locations), None, parsed).unwrap();
+		assert_eq!(graph.node_count(), 4);
+		// b-a and a-c in both directions, c-b and b-c as listed
+		assert_eq!(graph.link_count(), 6);
+		assert_eq!(graph.get_link(1, 0).unwrap().quality(), to_quality(0.5));
+		assert_eq!(graph.get_link(2, 0).unwrap().quality(), to_quality(1.0));
+		assert_eq!(graph.get_link(0, 2).unwrap().quality(), to_quality(0.25));
+		assert_eq!(locations.get_position(1), Some(&[1.0, 2.0, 0.0]));
+		assert_eq!(locations.get_position(2), Some(&[3.0, 4.0, 5.0]));
+		assert_eq!(locations.get_position(3), Some(&[6.0, 7.0, 0.0]));
+
+		assert!(parse_tsv("a b c d\n", None).is_err());
+		assert!(parse_tsv("a b x\n", None).is_err());
+		assert!(parse_tsv("a b\n", Some("a 1\n")).is_err());
+	}
+
+	#[test]
+	fn tsv_duplicate_edge_keeps_last() {
+		let graph = import(parse_tsv("a b 0.5\nb c\na b 0.25\n", None).unwrap());
+		assert_eq!(graph.node_count(), 3);
+		assert_eq!(graph.link_count(), 4);
+		assert_eq!(graph.get_link(0, 1).unwrap().quality(), to_quality(0.25));
+		// reverse direction was not listed
+		assert_eq!(graph.get_link(1, 0).unwrap().quality(), to_quality(0.25));
+	}
+}
@@ -0,0 +1,130 @@
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use rand::Rng;
+use rand::rngs::StdRng;
+
+use crate::graph::{Graph, ID};
+
+
+// management packet in transit
+#[derive(Clone)]
+pub struct Message {
+	pub from: ID,
+	pub to: ID,
+	pub payload: Vec<u8>,
+}
+
+struct QueuedMessage {
+	deliver_at: u32,
+	message: Message,
+}
+
+/*
+ * Per link packet queues for management traffic.
+ * Loss is decided when a packet is send,
+ * delivery happens after the link delay (in steps).
+ */
+pub struct LinkQueues {
+	// keyed by (to, from) to find all incoming links of a node
+	queues: BTreeMap<(ID, ID), VecDeque<QueuedMessage>>,
+	delays: HashMap<(ID, ID), u32>,
+	default_delay: u32,
+	time: u32,
+}
+
+impl LinkQueues {
+	pub fn new() -> Self {
+		Self {
+			queues: BTreeMap::new(),
+			delays: HashMap::new(),
+			default_delay: 1,
+			time: 0,
+		}
+	}
+
+	// drop all packets in transit
+	pub fn clear(&mut self) {
+		self.queues.clear();
+		self.time = 0;
+	}
+
+	// drop packets and delays of a node, higher node ids move down by one
+	pub fn remove_node(&mut self, id: ID) {
+		let shift = |n: ID| if n > id { n - 1 } else { n };
+
+		let queues = std::mem::take(&mut self.queues);
+		for ((to, from), mut queue) in queues {
+			if to != id && from != id {
+				for next in &mut queue {
+					next.message.from = shift(next.message.from);
+					next.message.to = shift(next.message.to);
+				}
+				self.queues.insert((shift(to), shift(from)), queue);
+			}
+		}
+
+		let delays = std::mem::take(&mut self.delays);
+		for ((from, to), delay) in delays {
+			if to != id && from != id {
+				self.delays.insert((shift(from), shift(to)), delay);
+			}
+		}
+	}
+
+	pub fn advance(&mut self) {
+		self.time += 1;
+	}
+
+	pub fn set_default_delay(&mut self, steps: u32) {
+		self.default_delay = steps;
+	}
+
+	pub fn get_delay(&self, from: ID, to: ID) -> u32 {
+		*self.delays.get(&(from, to)).unwrap_or(&self.default_delay)
+	}
+
+	pub fn set_delay(&mut self, from: ID, to: ID, steps: u32) {
+		self.delays.insert((from, to), steps);
+	}
+
+	// number of packets in transit
+	pub fn pending(&self) -> usize {
+		self.queues.values().fold(0, |acc, queue| acc + queue.len())
+	}
+
+	// returns false if the packet was lost
+	pub fn send(&mut self, graph: &Graph, from: ID, to: ID, payload: Vec<u8>, rng: &mut StdRng) -> bool {
+		if let Some(link) = graph.get_link(from, to) {
+			// link quality is the probability of a successful transmission
+			let p = (link.quality() as f32) / (u16::MAX as f32);
+			if rng.gen::<f32>() < p {
+				let deliver_at = self.time + self.get_delay(from, to);
+				self.queues.entry((to, from)).or_default().push_back(
+					QueuedMessage { deliver_at, message: Message { from, to, payload } }
+				);
+				return true;
+			}
+		}
+		false
+	}
+
+	// take all packets for a node that are due
+	pub fn receive(&mut self, graph: &Graph, id: ID) -> Vec<Message> {
+		let mut ret = Vec::new();
+		let time = self.time;
+
+		for (&(to, from), queue) in self.queues.range_mut((id, 0)..=(id, ID::MAX)) {
+			while let Some(next) = queue.front() {
+				if next.deliver_at > time {
+					break;
+				}
+				let next = queue.pop_front().unwrap();
+				// packets on links that vanished are lost
+				if graph.has_link(from, to) {
+					ret.push(next.message);
+				}
+			}
+		}
+
+		ret
+	}
+}
@@ -1,24 +1,26 @@
-use std::collections::HashMap;
+use std::collections::BTreeMap;
+use rand::Rng;
+use rand::rngs::StdRng;
 use crate::graph::ID;
 
 
 pub struct Locations {
-	pub data: HashMap<ID, [f32; 3]>
+	pub data: BTreeMap<ID, [f32; 3]>
 }
 
 impl Locations {
 	pub fn new() -> Self {
-		Self { data: HashMap::new() }
+		Self { data: BTreeMap::new() }
 	}
 
 	pub fn is_valid(pos: &[f32; 3]) -> bool {
-		!pos[0].is_nan() && pos[1].is_nan() && !pos[3].is_nan()
+		!pos[0].is_nan() && !pos[1].is_nan() && !pos[2].is_nan()
 	}
 
 	pub fn pos_distance(&self, p1: ID, p2: ID) -> Option<f32> {
 		if let (Some(a), Some(b)) = (self.data.get(&p1), self.data.get(&p2)) {
 			Some(((a[0] - b[0]).powi(2)
-				+ (a[1] - b[2]).powi(2)
+				+ (a[1] - b[1]).powi(2)
 				+ (a[2] - b[2]).powi(2)).sqrt())
 		} else {
 			None
@@ -29,8 +31,13 @@ impl Locations {
 		self.data.clear();
 	}
 
+	// higher node ids move down by one
 	pub fn remove_node(&mut self, id: ID) {
-		self.data.remove(&id);
+		let data = std::mem::take(&mut self.data);
+		self.data = data.into_iter()
+			.filter(|(k, _)| *k != id)
+			.map(|(k, v)| (if k > id { k - 1 } else { k }, v))
+			.collect();
 	}
 
 	pub fn insert(&mut self, id: ID, pos: [f32; 3]) {
@@ -66,6 +73,24 @@ impl Locations {
 		[c[0] / len, c[1] / len, c[2] / len]
 	}
 
+	// (min_x, min_y, max_x, max_y) of all positions
+	pub fn bounding_box(&self) -> [f32; 4] {
+		let mut b = [f32::INFINITY, f32::INFINITY, -f32::INFINITY, -f32::INFINITY];
+
+		for pos in self.data.values() {
+			b[0] = b[0].min(pos[0]);
+			b[1] = b[1].min(pos[1]);
+			b[2] = b[2].max(pos[0]);
+			b[3] = b[3].max(pos[1]);
+		}
+
+		if self.data.is_empty() {
+			[0.0, 0.0, 0.0, 0.0]
+		} else {
+			b
+		}
+	}
+
 	pub fn get_position(&self, id: ID) -> Option<&[f32; 3]> {
 		self.data.get(&id)
 	}
@@ -78,10 +103,10 @@ impl Locations {
 		}
 	}
 
-	pub fn randomize_positions_2d(&mut self, center: [f32; 3], range: f32) {
+	pub fn randomize_positions_2d(&mut self, center: [f32; 3], range: f32, rng: &mut StdRng) {
 		for val in self.data.values_mut() {
-			val[0] = center[0] + (2.0 * rand::random::<f32>() - 1.0) * range;
-			val[1] = center[1] + (2.0 * rand::random::<f32>() - 1.0) * range;
+			val[0] = center[0] + (2.0 * rng.gen::<f32>() - 1.0) * range;
+			val[1] = center[1] + (2.0 * rng.gen::<f32>() - 1.0) * range;
 			val[2] = 0.0;
 		}
 	}
@@ -11,6 +11,11 @@ mod exporter;
 mod movements;
 mod locations;
 mod meta;
+mod link_queues;
+mod propagation;
+mod experiment;
+mod recorder;
+mod failures;
 mod sim;
 mod cmd;
 mod progress;
@@ -37,7 +42,8 @@ const HELP_TEXT: &'static str = concat!(
 	"--help|-h         Display this help.\n",
 	"--version|-v      Display version.\n",
 	"--run <file>      Run commands from file.\n",
-	"--bind <address>  Bind command socket to address. (Default: 127.0.0.1:8011)\n"
+	"--bind <address>  Bind command socket to address. (Default: 127.0.0.1:8011)\n",
+	"--seed <number>   Seed of the random number generator for reproducible runs.\n"
 );
 
 
@@ -46,7 +52,13 @@ fn main() {
 	let args: Vec<String> = env::args().skip(1).collect();
 	let mut run_script = String::new();
 
-	if let Some((cmd, args)) = args.split_first() {
+	let mut seed = None;
+	let mut i = 0;
+
+	while i < args.len() {
+		let cmd = &args[i];
+		// value of an argument with parameter
+		let value = args.get(i + 1);
 		match cmd.as_ref() {
 			"-h" | "--help" => {
 				println!("{}", HELP_TEXT);
@@ -57,27 +69,46 @@ fn main() {
 				std::process::exit(0);
 			},
 			"--bind" => {
-				if args.len() == 1 {
-					cmd_address = args[0].clone();
-				} else if args.len() == 0 {
+				if let Some(address) = value {
+					cmd_address = address.clone();
+					i += 1;
+				} else {
 					println!("Address missing for \"--bind\".");
 					std::process::exit(1);
+				}
+			},
+			"--run" => {
+				if let Some(file) = value {
+					run_script = format!("run {}", file);
+					i += 1;
 				} else {
-					println!("Too many arguments for \"--bind\".");
+					println!("File missing for \"--run\".");
 					std::process::exit(1);
 				}
 			},
-			"--run" => {
-				run_script = format!("run {}", args.join(" "));
+			"--seed" => {
+				if let Some(Ok(n)) = value.map(|v| v.parse::<u64>()) {
+					seed = Some(n);
+					i += 1;
+				} else {
+					println!("Number missing for \"--seed\".");
+					std::process::exit(1);
+				}
 			},
 			_ => {
 				println!("Unknown argument: {}", cmd);
 				std::process::exit(1);
 			}
 		}
+		i += 1;
+	}
+
+	let mut state = GlobalState::new(&cmd_address);
+	if let Some(seed) = seed {
+		state.set_seed(seed);
 	}
 
-	let sim = Arc::new(Mutex::new(GlobalState::new(&cmd_address)));
+	let sim = Arc::new(Mutex::new(state));
 
 	// console
 	let cmd_handle = sim.clone();
@@ -15,8 +15,13 @@ impl Meta {
 		self.data.clear();
 	}
 
+	// higher node ids move down by one
 	pub fn remove_node(&mut self, id: ID) {
-		self.data.remove(&id);
+		let data = std::mem::take(&mut self.data);
+		self.data = data.into_iter()
+			.filter(|(k, _)| *k != id)
+			.map(|(k, v)| (if k > id { k - 1 } else { k }, v))
+			.collect();
 	}
 
 	pub fn insert(&mut self, id: ID, data: String) {
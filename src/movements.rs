@@ -1,45 +1,260 @@
-use std::collections::HashMap;
+use std::collections::BTreeMap;
+use std::f32;
+use rand::Rng;
+use rand::rngs::StdRng;
 
 use crate::locations::Locations;
-use crate::graph::{Graph, ID};
+use crate::graph::ID;
 
 
+// memory of the Gauss-Markov model (0: random walk, 1: linear motion)
+const GAUSS_MARKOV_ALPHA : f32 = 0.75;
+
+// steps a random walk keeps its direction
+const WALK_STEPS : u32 = 10;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Model {
+	Static,
+	RandomWaypoint,
+	RandomWalk,
+	GaussMarkov,
+}
+
+impl std::str::FromStr for Model {
+	type Err = ();
+
+	fn from_str(name: &str) -> Result<Self, Self::Err> {
+		match name {
+			"static" => Ok(Model::Static),
+			"waypoint" => Ok(Model::RandomWaypoint),
+			"walk" => Ok(Model::RandomWalk),
+			"gauss_markov" => Ok(Model::GaussMarkov),
+			_ => Err(())
+		}
+	}
+}
+
+impl Model {
+	pub fn name(&self) -> &'static str {
+		match self {
+			Model::Static => "static",
+			Model::RandomWaypoint => "waypoint",
+			Model::RandomWalk => "walk",
+			Model::GaussMarkov => "gauss_markov",
+		}
+	}
+}
+
+// area nodes move in (min_x, min_y, max_x, max_y)
+pub type Area = [f32; 4];
+
+fn random_range(rng: &mut StdRng, min: f32, max: f32) -> f32 {
+	min + rng.gen::<f32>() * (max - min)
+}
+
+// standard normal distributed value (Box-Muller)
+fn random_gauss(rng: &mut StdRng) -> f32 {
+	let u1 = 1.0 - rng.gen::<f32>();
+	let u2 = rng.gen::<f32>();
+	(-2.0 * u1.ln()).sqrt() * (2.0 * f32::consts::PI * u2).cos()
+}
+
 #[derive(Clone)]
 struct Movement {
-
+	model: Model,
+	// distance per step
+	speed: f32,
+	// steps to wait at a waypoint or after a walk
+	pause: u32,
+	pause_left: u32,
+	steps_left: u32,
+	target: [f32; 2],
+	// current speed and direction (radians)
+	velocity: f32,
+	direction: f32,
 }
 
 impl Movement {
-	fn new() -> Self {
-		Self {}
+	fn new(model: Model, speed: f32, pause: u32, rng: &mut StdRng) -> Self {
+		Self {
+			model,
+			speed,
+			pause,
+			pause_left: 0,
+			steps_left: 0,
+			target: [f32::NAN, f32::NAN],
+			velocity: speed,
+			direction: random_range(rng, 0.0, 2.0 * f32::consts::PI),
+		}
+	}
+
+	fn move_step(&mut self, pos: &mut [f32; 3], area: &Area, rng: &mut StdRng) {
+		if self.pause_left > 0 {
+			self.pause_left -= 1;
+			return;
+		}
+
+		match self.model {
+			Model::Static => {},
+			Model::RandomWaypoint => self.waypoint_step(pos, area, rng),
+			Model::RandomWalk => self.walk_step(pos, area, rng),
+			Model::GaussMarkov => self.gauss_markov_step(pos, area, rng),
+		}
 	}
 
-	fn move_step(&self, pos: &mut [f32; 3]) {
-		pos[0] += 0.01;
+	// move to a random target, pause and select a new target
+	fn waypoint_step(&mut self, pos: &mut [f32; 3], area: &Area, rng: &mut StdRng) {
+		if self.target[0].is_nan() {
+			self.target = [random_range(rng, area[0], area[2]), random_range(rng, area[1], area[3])];
+		}
+
+		let dx = self.target[0] - pos[0];
+		let dy = self.target[1] - pos[1];
+		let distance = (dx * dx + dy * dy).sqrt();
+
+		if distance <= self.speed {
+			pos[0] = self.target[0];
+			pos[1] = self.target[1];
+			self.target = [f32::NAN, f32::NAN];
+			self.pause_left = self.pause;
+		} else {
+			pos[0] += self.speed * dx / distance;
+			pos[1] += self.speed * dy / distance;
+		}
+	}
+
+	// move into a random direction for some steps and pause
+	fn walk_step(&mut self, pos: &mut [f32; 3], area: &Area, rng: &mut StdRng) {
+		if self.steps_left == 0 {
+			self.direction = random_range(rng, 0.0, 2.0 * f32::consts::PI);
+			self.steps_left = WALK_STEPS;
+		}
+
+		self.advance(pos, area, self.speed);
+
+		self.steps_left -= 1;
+		if self.steps_left == 0 {
+			self.pause_left = self.pause;
+		}
+	}
+
+	// speed and direction depend on previous values
+	// and drift towards the mean speed and the area center
+	fn gauss_markov_step(&mut self, pos: &mut [f32; 3], area: &Area, rng: &mut StdRng) {
+		let a = GAUSS_MARKOV_ALPHA;
+		let b = (1.0 - a * a).sqrt();
+
+		// near the border the mean direction points to the area center
+		let margin_x = 0.1 * (area[2] - area[0]);
+		let margin_y = 0.1 * (area[3] - area[1]);
+		let mean_direction = if pos[0] < area[0] + margin_x || pos[0] > area[2] - margin_x
+			|| pos[1] < area[1] + margin_y || pos[1] > area[3] - margin_y {
+			let cx = (area[0] + area[2]) / 2.0;
+			let cy = (area[1] + area[3]) / 2.0;
+			(cy - pos[1]).atan2(cx - pos[0])
+		} else {
+			self.direction
+		};
+
+		self.velocity = (a * self.velocity + (1.0 - a) * self.speed + b * self.speed * random_gauss(rng)).max(0.0);
+		self.direction = a * self.direction + (1.0 - a) * mean_direction + b * random_gauss(rng);
+
+		let velocity = self.velocity;
+		self.advance(pos, area, velocity);
+	}
+
+	// move along direction and reflect at the area border
+	fn advance(&mut self, pos: &mut [f32; 3], area: &Area, distance: f32) {
+		let mut x = pos[0] + distance * self.direction.cos();
+		let mut y = pos[1] + distance * self.direction.sin();
+
+		if x < area[0] || x > area[2] {
+			x = x.max(area[0]).min(area[2]);
+			self.direction = f32::consts::PI - self.direction;
+		}
+
+		if y < area[1] || y > area[3] {
+			y = y.max(area[1]).min(area[3]);
+			self.direction = -self.direction;
+		}
+
+		pos[0] = x;
+		pos[1] = y;
 	}
 }
 
 pub struct Movements {
-	data: HashMap<u32, Movement>
+	data: BTreeMap<u32, Movement>,
+	area: Option<Area>,
 }
 
 impl Movements {
 	pub fn new() -> Self {
-		Self { data: HashMap::new() }
+		Self { data: BTreeMap::new(), area: None }
 	}
 
 	pub fn clear(&mut self) {
 		self.data.clear();
+		self.area = None;
 	}
 
+	// higher node ids move down by one
 	pub fn remove_node(&mut self, id: ID) {
-		self.data.remove(&id);
+		let data = std::mem::take(&mut self.data);
+		self.data = data.into_iter()
+			.filter(|(k, _)| *k != id)
+			.map(|(k, v)| (if k > id { k - 1 } else { k }, v))
+			.collect();
 	}
 
-	pub fn step(&self, locations: &mut Locations) {
-		for (id, movement) in self.data.iter() {
+	// number of moving nodes
+	pub fn count(&self) -> usize {
+		self.data.len()
+	}
+
+	pub fn get_area(&self) -> Option<Area> {
+		self.area
+	}
+
+	pub fn set_area(&mut self, area: Area) {
+		self.area = Some(area);
+	}
+
+	// set mobility model of a node
+	pub fn set_model(&mut self, id: ID, model: Model, speed: f32, pause: u32, rng: &mut StdRng) {
+		if model == Model::Static {
+			self.data.remove(&id);
+		} else {
+			self.data.insert(id, Movement::new(model, speed, pause, rng));
+		}
+	}
+
+	// set mobility model of all nodes with a location
+	pub fn set_model_all(&mut self, locations: &Locations, model: Model, speed: f32, pause: u32, rng: &mut StdRng) {
+		for id in locations.data.keys() {
+			self.set_model(*id, model, speed, pause, rng);
+		}
+	}
+
+	pub fn step(&mut self, locations: &mut Locations, rng: &mut StdRng) {
+		if self.data.is_empty() {
+			return;
+		}
+
+		// default area is the bounding box of all nodes
+		let area = match self.area {
+			Some(area) => area,
+			None => {
+				let area = locations.bounding_box();
+				self.area = Some(area);
+				area
+			}
+		};
+
+		for (id, movement) in self.data.iter_mut() {
 			if let Some(location) = locations.data.get_mut(id) {
-				movement.move_step(location);
+				movement.move_step(location, &area, rng);
 			}
 		}
 	}
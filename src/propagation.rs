@@ -0,0 +1,134 @@
+use std::collections::HashMap;
+use std::f32;
+use rand::Rng;
+use rand::rngs::StdRng;
+
+use crate::graph::ID;
+
+
+// speed of light in m/s
+const LIGHT_SPEED : f32 = 299_792_458.0;
+
+// path loss at 1m reference distance for 2.4GHz (dB)
+const REFERENCE_LOSS : f32 = 40.0;
+
+// received power above sensitivity for maximum link quality (dB)
+const FADE_MARGIN : f32 = 20.0;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Model {
+	// full quality up to half the range, then linear decrease
+	UnitDisk,
+	// path loss grows with exponent * log(distance)
+	LogDistance { exponent: f32 },
+	// free space up to the crossover distance, then 40dB per decade
+	TwoRayGround { height: f32 },
+	// log distance with a gaussian shadowing per link direction
+	Shadowing { exponent: f32, sigma: f32 },
+}
+
+impl Model {
+	pub fn name(&self) -> &'static str {
+		match self {
+			Model::UnitDisk => "unit_disk",
+			Model::LogDistance { .. } => "log_distance",
+			Model::TwoRayGround { .. } => "two_ray",
+			Model::Shadowing { .. } => "shadowing",
+		}
+	}
+}
+
+/*
+ * Map distance between nodes to link quality.
+ * Distances are in km. Except for the unit disk model,
+ * a link exists if the received power is above the sensitivity.
+ */
+pub struct Propagation {
+	model: Model,
+	// transmission power (dBm)
+	pub tx_power: f32,
+	// minimum received power (dBm)
+	pub sensitivity: f32,
+	// frequency in Hz
+	pub frequency: f32,
+	// shadowing of each link direction (dB)
+	shadows: HashMap<(ID, ID), f32>,
+}
+
+impl Propagation {
+	pub fn new() -> Self {
+		Self {
+			model: Model::UnitDisk,
+			tx_power: 20.0,
+			sensitivity: -85.0,
+			frequency: 2.4e9,
+			shadows: HashMap::new(),
+		}
+	}
+
+	pub fn model(&self) -> Model {
+		self.model
+	}
+
+	pub fn set_model(&mut self, model: Model) {
+		self.shadows.clear();
+		self.model = model;
+	}
+
+	// forget shadowing values, e.g. when node ids change
+	pub fn clear(&mut self) {
+		self.shadows.clear();
+	}
+
+	// path loss in dB, distance in meters
+	fn path_loss(&mut self, from: ID, to: ID, distance: f32, rng: &mut StdRng) -> f32 {
+		// avoid log of 0
+		let d = distance.max(1.0);
+
+		match self.model {
+			Model::UnitDisk => 0.0,
+			Model::LogDistance { exponent } => {
+				REFERENCE_LOSS + 10.0 * exponent * d.log10()
+			},
+			Model::TwoRayGround { height } => {
+				let lambda = LIGHT_SPEED / self.frequency;
+				let crossover = 4.0 * f32::consts::PI * height * height / lambda;
+				if d < crossover {
+					// free space (Friis)
+					20.0 * (4.0 * f32::consts::PI * d / lambda).log10()
+				} else {
+					40.0 * d.log10() - 20.0 * (height * height).log10()
+				}
+			},
+			Model::Shadowing { exponent, sigma } => {
+				let shadow = *self.shadows.entry((from, to)).or_insert_with(|| {
+					// standard normal distribution (Box-Muller)
+					let u1 = 1.0 - rng.gen::<f32>();
+					let u2 = rng.gen::<f32>();
+					sigma * (-2.0 * u1.ln()).sqrt() * (2.0 * f32::consts::PI * u2).cos()
+				});
+				REFERENCE_LOSS + 10.0 * exponent * d.log10() + shadow
+			}
+		}
+	}
+
+	// link quality of link from => to in distance (km), None if there is no link
+	pub fn link_quality(&mut self, from: ID, to: ID, distance: f32, range: f32, rng: &mut StdRng) -> Option<u16> {
+		if distance > range {
+			return None;
+		}
+
+		if self.model == Model::UnitDisk {
+			let q = (2.0 * (range - distance) / range).min(1.0);
+			return Some(((u16::MAX as f32) * q).max(1.0) as u16);
+		}
+
+		let rx_power = self.tx_power - self.path_loss(from, to, 1000.0 * distance, rng);
+		if rx_power < self.sensitivity {
+			None
+		} else {
+			let q = ((rx_power - self.sensitivity) / FADE_MARGIN).min(1.0);
+			Some(((u16::MAX as f32) * q).max(1.0) as u16)
+		}
+	}
+}
@@ -0,0 +1,125 @@
+use std::fs::File;
+use std::io::Write;
+
+use crate::utils::MyError;
+use crate::graph::ID;
+
+
+// Evaluation results after a simulation step
+pub struct Sample {
+	pub step: u32,
+	pub arrived: f32,
+	pub stretch: f32,
+	pub loops: u32,
+	// changes of the routing state
+	pub convergence: f32,
+	// management traffic since the previous sample
+	pub packets: u64,
+	pub bytes: u64,
+}
+
+/*
+ * Record a time series of evaluation results
+ * every interval simulation steps.
+ */
+pub struct Recorder {
+	// 0 disables recording
+	interval: u32,
+	// test packets per evaluation
+	samples: u32,
+	series: Vec<Sample>,
+	// traffic counters at the previous sample
+	last_packets: u64,
+	last_bytes: u64,
+	// next hops at the previous sample
+	pub routes: Vec<ID>,
+}
+
+impl Recorder {
+	pub fn new() -> Self {
+		Self {
+			interval: 0,
+			samples: 1000,
+			series: vec![],
+			last_packets: 0,
+			last_bytes: 0,
+			routes: vec![],
+		}
+	}
+
+	// start a new series
+	pub fn set(&mut self, interval: u32, samples: u32) {
+		self.interval = interval;
+		self.samples = samples;
+		self.clear();
+	}
+
+	pub fn clear(&mut self) {
+		self.series.clear();
+		self.last_packets = 0;
+		self.last_bytes = 0;
+		self.routes.clear();
+	}
+
+	pub fn interval(&self) -> u32 {
+		self.interval
+	}
+
+	pub fn samples(&self) -> u32 {
+		self.samples
+	}
+
+	pub fn series(&self) -> &[Sample] {
+		&self.series
+	}
+
+	pub fn is_due(&self, step: u32) -> bool {
+		self.interval > 0 && step.is_multiple_of(self.interval)
+	}
+
+	// total traffic counters are turned into the traffic since the last sample
+	pub fn add(&mut self, mut sample: Sample) {
+		let packets = sample.packets;
+		let bytes = sample.bytes;
+		sample.packets = packets.saturating_sub(self.last_packets);
+		sample.bytes = bytes.saturating_sub(self.last_bytes);
+		self.last_packets = packets;
+		self.last_bytes = bytes;
+		self.series.push(sample);
+	}
+
+	// write series as JSON or CSV, depending on the file extension
+	pub fn export(&self, path: &str) -> Result<(), MyError> {
+		fn number(n: f32) -> String {
+			if n.is_finite() {
+				n.to_string()
+			} else {
+				"null".to_string()
+			}
+		}
+
+		let mut file = File::create(path)?;
+
+		if path.ends_with(".json") {
+			write!(file, "[")?;
+			for (i, s) in self.series.iter().enumerate() {
+				if i > 0 {
+					write!(file, ",")?;
+				}
+				write!(file, "\n{{\"step\": {}, \"arrived\": {}, \"stretch\": {}, \"loops\": {}, \"convergence\": {}, \"packets\": {}, \"bytes\": {}}}",
+					s.step, number(s.arrived), number(s.stretch), s.loops,
+					number(s.convergence), s.packets, s.bytes)?;
+			}
+			writeln!(file, "\n]")?;
+		} else {
+			writeln!(file, "step,arrived,stretch,loops,convergence,packets,bytes")?;
+			for s in &self.series {
+				writeln!(file, "{},{},{},{},{},{},{}",
+					s.step, s.arrived, s.stretch, s.loops,
+					s.convergence, s.packets, s.bytes)?;
+			}
+		}
+
+		Ok(())
+	}
+}
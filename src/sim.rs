@@ -3,6 +3,8 @@ use std::time::Duration;
 use std::collections::HashMap;
 use std::f32;
 use serde_json::Value;
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
 
 use crate::graph::{Graph, Link, ID};
 use crate::algorithms::random_routing::RandomRouting;
@@ -12,20 +14,47 @@ use crate::utils::{get_str, get_u64, print_unknown_key};
 use crate::movements::Movements;
 use crate::locations::Locations;
 use crate::meta::Meta;
+use crate::link_queues::{LinkQueues, Message};
+use crate::stats::TrafficStats;
+use crate::propagation::Propagation;
+use crate::recorder::{Recorder, Sample};
+use crate::failures::Failures;
 
 
 // default distance, too small confuses d3.js
 const NODE_SPACING : f32 = 50.0;
 
+// steps below the convergence threshold until the network is considered stable
+const STABLE_STEPS : u32 = 10;
+
+// Derive links from node positions
+#[derive(Clone, Copy)]
+pub struct RadioModel {
+	// maximum link distance
+	pub range: f32,
+	// update links every n simulation steps
+	pub interval: u32,
+}
+
 pub struct GlobalState {
 	pub graph: Graph,
 	pub locations: Locations,
 	pub movements: Movements,
 	pub meta: Meta,
+	pub queues: LinkQueues,
+	pub traffic: TrafficStats,
 	pub algorithm: Box<RoutingAlgorithm>,
+	pub algorithm_name: &'static str,
+	pub radio: Option<RadioModel>,
+	pub propagation: Propagation,
 	pub test: EvalPaths,
 	pub debug_path: DebugPath,
+	pub recorder: Recorder,
+	pub failures: Failures,
 	pub sim_steps: u32,
+	// all randomness is taken from rng for reproducible runs
+	pub seed: u64,
+	pub rng: StdRng,
 	pub abort_simulation: bool,
 	pub show_progress: bool,
 	pub cmd_address: String,
@@ -34,15 +63,25 @@ pub struct GlobalState {
 
 impl GlobalState {
 	pub fn new(cmd_address: &str) -> Self {
+		let seed = rand::random::<u64>();
 		Self {
 			graph: Graph::new(),
 			locations: Locations::new(),
 			movements: Movements::new(),
 			meta: Meta::new(),
+			queues: LinkQueues::new(),
+			traffic: TrafficStats::new(),
 			algorithm: Box::new(RandomRouting::new()),
+			algorithm_name: "random",
+			radio: None,
+			propagation: Propagation::new(),
 			test: EvalPaths::new(),
 			debug_path: DebugPath::new(),
+			recorder: Recorder::new(),
+			failures: Failures::new(),
 			sim_steps: 0,
+			seed,
+			rng: StdRng::seed_from_u64(seed),
 			abort_simulation: false,
 			show_progress: false,
 			export_path: "graph.json".to_string(),
@@ -50,18 +89,206 @@ impl GlobalState {
 		}
 	}
 
+	// restart random number generator
+	pub fn set_seed(&mut self, seed: u64) {
+		self.seed = seed;
+		self.rng = StdRng::seed_from_u64(seed);
+	}
+
+	// run a single simulation step
+	pub fn step(&mut self) {
+		let mut io = Io::new(&self.graph, &mut self.queues, &mut self.traffic, &mut self.rng);
+		self.algorithm.step(&mut io);
+		self.queues.advance();
+		self.traffic.next_step();
+		self.movements.step(&mut self.locations, &mut self.rng);
+		self.sim_steps += 1;
+
+		let failures_changed = self.failures.update(self.sim_steps, &self.graph, &mut self.rng);
+		let radio_due = self.radio.is_some_and(|radio| self.sim_steps.is_multiple_of(radio.interval));
+
+		if radio_due {
+			self.update_radio_links();
+		} else if failures_changed {
+			self.apply_failures();
+		}
+
+		if self.recorder.is_due(self.sim_steps) {
+			self.record_sample();
+		}
+	}
+
+	// next hop of every pair of source and destination
+	fn route_table(&self) -> Vec<ID> {
+		let len = self.graph.node_count();
+		let mut table = Vec::with_capacity(len * len);
+
+		for source in 0..len as ID {
+			for destination in 0..len as ID {
+				let packet = TestPacket::new(source, source, source, destination);
+				table.push(self.algorithm.route(&packet).unwrap_or(ID::MAX));
+			}
+		}
+
+		table
+	}
+
+	// fraction of next hops in table that differ from prev
+	fn route_changes(table: &[ID], prev: &[ID]) -> f32 {
+		if table.is_empty() {
+			return 0.0;
+		}
+
+		let changes = if table.len() != prev.len() {
+			table.len()
+		} else {
+			table.iter().zip(prev).filter(|(a, b)| a != b).count()
+		};
+
+		(changes as f32) / (table.len() as f32)
+	}
+
+	// Measure of routing changes, as reported by the algorithm or
+	// from the next hops changed since routes were taken.
+	pub fn convergence(&self, routes: &mut Vec<ID>) -> f32 {
+		if let Some(convergence) = self.algorithm.convergence() {
+			convergence
+		} else {
+			let table = self.route_table();
+			let changes = Self::route_changes(&table, routes);
+			*routes = table;
+			changes
+		}
+	}
+
+	// Run simulation steps until the convergence measure stays below threshold.
+	// Returns the steps until the network was stable and the steps run.
+	pub fn run_until_converged(&mut self, max_steps: u32, threshold: f32) -> (Option<u32>, u32) {
+		let mut stable = 0;
+
+		// remember current routes
+		let mut routes = Vec::new();
+		self.convergence(&mut routes);
+
+		for step in 1..=max_steps {
+			if self.abort_simulation {
+				return (None, step - 1);
+			}
+
+			self.step();
+
+			if self.convergence(&mut routes) <= threshold {
+				stable += 1;
+				if stable == STABLE_STEPS {
+					return (Some(step - STABLE_STEPS), step);
+				}
+			} else {
+				stable = 0;
+			}
+		}
+
+		(None, max_steps)
+	}
+
+	// evaluate current routing state and add it to the time series
+	pub fn record_sample(&mut self) {
+		let mut test = EvalPaths::new();
+		test.set_threads(self.test.threads());
+		test.set_max_stretch(self.test.max_stretch());
+
+		// reactive algorithms are only tested with their current routes
+		let algorithm = &self.algorithm;
+		// own random numbers, recording must not change the simulation
+		let mut rng = StdRng::seed_from_u64(self.seed ^ (self.sim_steps as u64));
+		test.run_samples(&self.graph, |p| algorithm.route(p), self.recorder.samples() as usize, &mut rng);
+
+		// routes of the previous sample
+		let mut routes = std::mem::take(&mut self.recorder.routes);
+		let convergence = self.convergence(&mut routes);
+		self.recorder.routes = routes;
+
+		self.recorder.add(Sample {
+			step: self.sim_steps,
+			arrived: test.arrived(),
+			stretch: test.stretch(),
+			loops: test.loops(),
+			convergence,
+			packets: self.traffic.total.packets,
+			bytes: self.traffic.total.bytes,
+		});
+	}
+
+	// remove links of active failures and restore links of recovered failures
+	pub fn apply_failures(&mut self) -> (usize, usize) {
+		let node_count = self.graph.node_count();
+		let mut links = self.graph.links.clone();
+
+		// skip links of nodes that were removed meanwhile
+		links.extend(self.failures.take_removed().into_iter().filter(|link| {
+			(link.from as usize) < node_count && (link.to as usize) < node_count
+		}));
+
+		let links = self.failures.filter_links(links, &self.locations);
+		self.replace_links(links)
+	}
+
+	// remove all failures and restore their links
+	pub fn clear_failures(&mut self) -> (usize, usize) {
+		let node_count = self.graph.node_count();
+		let mut links = self.graph.links.clone();
+
+		links.extend(self.failures.take_removed().into_iter().filter(|link| {
+			(link.from as usize) < node_count && (link.to as usize) < node_count
+		}));
+
+		self.failures.clear();
+		self.replace_links(links)
+	}
+
+	// set new links and inform the routing algorithm about changes
+	fn replace_links(&mut self, links: Vec<Link>) -> (usize, usize) {
+		let old = self.graph.clone();
+		self.graph.set_links(links);
+
+		let mut added = 0;
+		let mut removed = 0;
+
+		for link in &old.links {
+			if !self.graph.has_link(link.from, link.to) {
+				self.algorithm.link_removed(link.from, link.to);
+				removed += 1;
+			}
+		}
+
+		for link in &self.graph.links {
+			if !old.has_link(link.from, link.to) {
+				self.algorithm.link_added(link.from, link.to);
+				added += 1;
+			}
+		}
+
+		(added, removed)
+	}
+
 	pub fn remove_node(&mut self, id: ID) {
+		self.failures.remove_node(id);
 		self.graph.remove_node(id);
 		self.locations.remove_node(id);
 		self.movements.remove_node(id);
 		self.meta.remove_node(id);
+		self.queues.remove_node(id);
+		self.propagation.clear();
 	}
 
 	pub fn clear(&mut self) {
+		self.failures.clear();
 		self.graph.clear();
 		self.locations.clear();
 		self.movements.clear();
 		self.meta.clear();
+		self.queues.clear();
+		self.traffic.clear();
+		self.propagation.clear();
 	}
 
 	pub fn get_mean_link_distance(&self) -> (f32, f32) {
@@ -98,14 +325,51 @@ impl GlobalState {
 					continue;
 				}
 				if let Some(distance) = self.locations.pos_distance(i, j) {
-					if distance <= range {
-						self.graph.connect(i, j);
+					if let Some(quality) = self.propagation.link_quality(i, j, distance, range, &mut self.rng) {
+						self.graph.add_link(i, j, quality);
 					}
 				}
 			}
 		}
 	}
 
+	// Rebuild links between nodes with positions from the radio model
+	// and notify the algorithm about changes. Returns (added, removed) links.
+	pub fn update_radio_links(&mut self) -> (usize, usize) {
+		let radio = match self.radio {
+			Some(radio) => radio,
+			None => return (0, 0),
+		};
+
+		let node_count = self.graph.node_count() as ID;
+		let locations = &self.locations;
+		let propagation = &mut self.propagation;
+		let rng = &mut self.rng;
+
+		// keep links of nodes without position, including those removed by failures
+		let failed = self.failures.take_removed();
+		let mut links : Vec<Link> = self.graph.links.iter().chain(failed.iter()).filter(|link| {
+			(link.from < node_count && link.to < node_count)
+				&& (locations.get_position(link.from).is_none() || locations.get_position(link.to).is_none())
+		}).cloned().collect();
+
+		for from in 0..node_count {
+			for to in 0..node_count {
+				if from == to {
+					continue;
+				}
+				if let Some(distance) = locations.pos_distance(from, to) {
+					if let Some(quality) = propagation.link_quality(from, to, distance, radio.range, rng) {
+						links.push(Link::new(from, to, quality));
+					}
+				}
+			}
+		}
+
+		let links = self.failures.filter_links(links, &self.locations);
+		self.replace_links(links)
+	}
+
 	pub fn add_line(&mut self, count: u32, close: bool) {
 		if count < 1 {
 			return;
@@ -144,7 +408,7 @@ impl GlobalState {
 			//if i > 0 {
 				// Connect node with random previous node
 				loop {
-					let j = rand::random::<ID>() % i;
+					let j = self.rng.gen::<ID>() % i;
 					if i != j && !self.graph.has_link((offset + i) as ID, (offset + j) as ID) {
 						self.graph.connect((offset + i) as ID, (offset + j) as ID);
 						break;
@@ -157,8 +421,8 @@ impl GlobalState {
 		if count > 2 {
 			for _ in 0..std::cmp::min(intra, (count * (count - 1)) / 2 - (count + 1)) {
 				loop {
-					let i = rand::random::<ID>() % count;
-					let j = rand::random::<ID>() % count;
+					let i = self.rng.gen::<ID>() % count;
+					let j = self.rng.gen::<ID>() % count;
 					if i != j && !self.graph.has_link((offset + i) as ID, (offset + j) as ID) {
 						self.graph.connect((offset + i) as ID, (offset + j) as ID);
 						break;
@@ -248,6 +512,261 @@ impl GlobalState {
 		}
 	}
 
+	// Place nodes at random in a square with one node per NODE_SPACING^2 on average.
+	// Returns the width of the square.
+	fn add_random_positions(&mut self, offset: u32, count: u32) -> f32 {
+		let width = NODE_SPACING * (count as f32).sqrt();
+		for i in 0..count {
+			self.locations.insert(offset + i, [
+				width * self.rng.gen::<f32>(),
+				width * self.rng.gen::<f32>(),
+				0.0
+			]);
+		}
+		width
+	}
+
+	// Erdős–Rényi graph: connect each pair of nodes with probability p
+	pub fn add_gnp(&mut self, count: u32, p: f32) {
+		let offset = self.graph.node_count() as u32;
+		self.graph.add_nodes(count);
+		self.add_random_positions(offset, count);
+
+		for i in 0..count {
+			for j in (i + 1)..count {
+				if self.rng.gen::<f32>() < p {
+					self.graph.connect(offset + i, offset + j);
+				}
+			}
+		}
+	}
+
+	// Watts–Strogatz small world: ring of nodes connected to the k nearest neighbors,
+	// each link is rewired to a random node with probability beta
+	pub fn add_small_world(&mut self, count: u32, k: u32, beta: f32) {
+		if count < 1 {
+			return;
+		}
+
+		let offset = self.graph.node_count() as u32;
+		self.graph.add_nodes(count);
+
+		let r = NODE_SPACING * (count as f32) / (2.0 * f32::consts::PI);
+		for i in 0..count {
+			let a = 2.0 * (i as f32) * f32::consts::PI / (count as f32);
+			self.locations.insert(offset + i, [r * a.sin(), r * a.cos(), 0.0]);
+		}
+
+		// k / 2 neighbors on each side
+		let half = (k / 2).min((count - 1) / 2);
+		for d in 1..(half + 1) {
+			for i in 0..count {
+				let a = offset + i;
+				let mut b = offset + (i + d) % count;
+
+				if self.rng.gen::<f32>() < beta {
+					// give up rewiring on (almost) complete graphs
+					for _ in 0..count {
+						let c = offset + self.rng.gen_range(0, count);
+						if c != a && !self.graph.has_link(a, c) {
+							b = c;
+							break;
+						}
+					}
+				}
+
+				self.graph.connect(a, b);
+			}
+		}
+	}
+
+	// Barabási–Albert scale free graph: every new node connects
+	// to m nodes with a probability proportional to their degree
+	pub fn add_scale_free(&mut self, count: u32, m: u32) {
+		if count < 1 {
+			return;
+		}
+
+		let offset = self.graph.node_count() as u32;
+		self.graph.add_nodes(count);
+		self.add_random_positions(offset, count);
+
+		let m = m.max(1).min(count - 1);
+
+		// every node appears once per link end
+		let mut ends : Vec<ID> = vec![];
+
+		// fully connected initial nodes
+		for i in 0..(m + 1).min(count) {
+			for j in 0..i {
+				self.graph.connect(offset + i, offset + j);
+				ends.push(offset + i);
+				ends.push(offset + j);
+			}
+		}
+
+		for i in (m + 1)..count {
+			let a = offset + i;
+			let mut targets = vec![];
+			while targets.len() < m as usize {
+				let b = ends[self.rng.gen_range(0, ends.len())];
+				if !targets.contains(&b) {
+					targets.push(b);
+				}
+			}
+
+			for b in targets {
+				self.graph.connect(a, b);
+				ends.push(a);
+				ends.push(b);
+			}
+		}
+	}
+
+	// Random geometric graph: connect nodes closer than radius,
+	// radius is relative to the width of the area (0..1)
+	pub fn add_random_geometric(&mut self, count: u32, radius: f32) {
+		let offset = self.graph.node_count() as u32;
+		self.graph.add_nodes(count);
+		let range = radius * self.add_random_positions(offset, count);
+
+		for i in 0..count {
+			for j in (i + 1)..count {
+				if let Some(distance) = self.locations.pos_distance(offset + i, offset + j) {
+					if distance < range {
+						self.graph.connect(offset + i, offset + j);
+					}
+				}
+			}
+		}
+	}
+
+	/*
+	 * Community mesh network like imported Freifunk maps:
+	 * Neighborhoods of nodes with short links to the nearest nodes,
+	 * a backbone node per neighborhood with long rooftop links
+	 * to neighborhoods nearby and a few gateways to the backbone.
+	 */
+	pub fn add_community_mesh(&mut self, count: u32, density: u32, cluster_size: u32) {
+		if count < 1 {
+			return;
+		}
+
+		let offset = self.graph.node_count() as u32;
+		self.graph.add_nodes(count);
+
+		let cluster_count = (count / cluster_size.max(1)).max(1);
+		let gateway_count = if count > 2 * cluster_count { (cluster_count / 4).max(1) } else { 0 };
+		let member_count = count - gateway_count;
+
+		// neighborhoods are spread over an area four times the size of add_random_positions()
+		let width = 2.0 * NODE_SPACING * (count as f32).sqrt();
+		let mut centers = vec![];
+		for _ in 0..cluster_count {
+			centers.push([width * self.rng.gen::<f32>(), width * self.rng.gen::<f32>(), 0.0]);
+		}
+
+		// assign nodes to neighborhoods of different size, at least one node each
+		let weights : Vec<f32> = (0..cluster_count).map(|_| 0.5 + self.rng.gen::<f32>()).collect();
+		let total : f32 = weights.iter().sum();
+		let mut clusters : Vec<Vec<ID>> = vec![vec![]; cluster_count as usize];
+		for i in 0..member_count {
+			let c = if i < cluster_count {
+				i as usize
+			} else {
+				let mut r = total * self.rng.gen::<f32>();
+				let mut c = 0;
+				while c + 1 < weights.len() && r >= weights[c] {
+					r -= weights[c];
+					c += 1;
+				}
+				c
+			};
+			clusters[c].push(offset + i);
+		}
+
+		let mut positions = vec![[0.0f32; 3]; count as usize];
+		let distance = |a: &[f32; 3], b: &[f32; 3]| ((a[0] - b[0]).powi(2) + (a[1] - b[1]).powi(2)).sqrt();
+
+		// place nodes in a disk around the neighborhood center
+		for (c, nodes) in clusters.iter().enumerate() {
+			let radius = NODE_SPACING * (nodes.len() as f32).sqrt() / 2.0;
+			for id in nodes {
+				let r = radius * self.rng.gen::<f32>().sqrt();
+				let a = 2.0 * f32::consts::PI * self.rng.gen::<f32>();
+				positions[(id - offset) as usize] = [centers[c][0] + r * a.cos(), centers[c][1] + r * a.sin(), 0.0];
+			}
+		}
+
+		// short links to the nearest nodes of the neighborhood
+		for nodes in &clusters {
+			for (i, a) in nodes.iter().enumerate() {
+				let pa = positions[(a - offset) as usize];
+				let mut others : Vec<(f32, usize)> = nodes.iter().enumerate()
+					.filter(|(j, _)| *j != i)
+					.map(|(j, b)| (distance(&pa, &positions[(b - offset) as usize]), j))
+					.collect();
+				others.sort_by(|x, y| x.partial_cmp(y).unwrap());
+
+				for (_, j) in others.iter().take(density as usize) {
+					self.graph.connect(*a, nodes[*j]);
+				}
+
+				// keep the neighborhood connected
+				if let Some((_, j)) = others.iter().find(|(_, j)| *j < i) {
+					self.graph.connect(*a, nodes[*j]);
+				}
+			}
+		}
+
+		// backbone node closest to the neighborhood center
+		let mut backbone = vec![];
+		for (c, nodes) in clusters.iter().enumerate() {
+			let id = *nodes.iter().min_by(|a, b| {
+				let da = distance(&centers[c], &positions[(*a - offset) as usize]);
+				let db = distance(&centers[c], &positions[(*b - offset) as usize]);
+				da.partial_cmp(&db).unwrap()
+			}).unwrap();
+			self.meta.insert(id, format!("{{\"label\": \"backbone-{}\"}}", c));
+			backbone.push(id);
+		}
+
+		// neighborhoods ordered by distance to a point
+		let nearest = |p: &[f32; 3]| -> Vec<usize> {
+			let mut order : Vec<usize> = (0..centers.len()).collect();
+			order.sort_by(|a, b| distance(p, &centers[*a]).partial_cmp(&distance(p, &centers[*b])).unwrap());
+			order
+		};
+
+		// rooftop links to the two nearest neighborhoods
+		// and to the nearest of the previous ones to connect all neighborhoods
+		for c in 0..centers.len() {
+			let order = nearest(&centers[c]);
+			for d in order.iter().skip(1).take(2) {
+				self.graph.connect(backbone[c], backbone[*d]);
+			}
+			if let Some(d) = order.iter().find(|d| **d < c) {
+				self.graph.connect(backbone[c], backbone[*d]);
+			}
+		}
+
+		// gateways next to a random neighborhood center, linked to the three nearest backbone nodes
+		for g in 0..gateway_count {
+			let id = offset + member_count + g;
+			let c = self.rng.gen_range(0, centers.len());
+			let pos = [centers[c][0] + NODE_SPACING * self.rng.gen::<f32>(), centers[c][1] + NODE_SPACING * self.rng.gen::<f32>(), 0.0];
+			positions[(id - offset) as usize] = pos;
+			self.meta.insert(id, format!("{{\"label\": \"gateway-{}\"}}", g));
+			for d in nearest(&pos).iter().take(3) {
+				self.graph.connect(id, backbone[*d]);
+			}
+		}
+
+		for (i, pos) in positions.into_iter().enumerate() {
+			self.locations.insert(offset + i as u32, pos);
+		}
+	}
+
 	// move out
 	pub fn graph_to_json(&self, graph: &Graph, ret: &mut String) -> Result<(), std::fmt::Error>
 	{
@@ -303,7 +822,7 @@ impl TestPacket {
 	}
 }
 
-pub trait RoutingAlgorithm : Send {
+pub trait RoutingAlgorithm : Send + Sync {
 	fn get_node(&self, _id: ID, _key: &str, _out: &mut std::fmt::Write) -> Result<(), std::fmt::Error> {
 		Ok(())
 	}
@@ -324,7 +843,7 @@ pub trait RoutingAlgorithm : Send {
 
 	// Called to initialize the states or
 	// when the number of nodes changes
-	fn reset(&mut self, len: usize);
+	fn reset(&mut self, len: usize, rng: &mut StdRng);
 
 	// Exchange maintenance traffic for each
 	// link how to combine links with stats?
@@ -334,6 +853,44 @@ pub trait RoutingAlgorithm : Send {
 	fn route(&self, _packet: &TestPacket) -> Option<ID> {
 		None
 	}
+
+	// Measure of how much the routing state changed in the last step, 0 if stable.
+	// Without it, the fraction of changed next hops of all routes is used.
+	fn convergence(&self) -> Option<f32> {
+		None
+	}
+
+	// Called when the radio model adds a link
+	fn link_added(&mut self, _from: ID, _to: ID) {
+	}
+
+	// Called when the radio model removes a link
+	fn link_removed(&mut self, _from: ID, _to: ID) {
+	}
+
+	// Reactive algorithms are tested with forward() instead of route()
+	fn is_reactive(&self) -> bool {
+		false
+	}
+
+	// Handle test packet at packet.receiver. The algorithm may buffer
+	// the packet and send management packets to discover a route.
+	fn forward(&mut self, packet: &TestPacket, _io: &mut Io) -> Forward {
+		match self.route(packet) {
+			Some(next) => Forward::Next(next),
+			None => Forward::Drop,
+		}
+	}
+}
+
+// Decision of a reactive algorithm for a test packet
+#[derive(Clone, Copy, PartialEq)]
+pub enum Forward {
+	// send to next hop
+	Next(ID),
+	// wait for a route discovery
+	Buffered,
+	Drop,
 }
 
 fn is_smaller(d: &Duration, secs: u64, millis: u32) -> bool {
@@ -350,28 +907,66 @@ fn is_smaller(d: &Duration, secs: u64, millis: u32) -> bool {
 
 pub struct Io<'a> {
 	graph: &'a Graph,
-	//time?
+	queues: &'a mut LinkQueues,
+	traffic: &'a mut TrafficStats,
+	rng: &'a mut StdRng,
 }
 
 impl<'a> Io<'a> {
-	pub fn new(graph: &'a Graph) -> Self {
+	pub fn new(graph: &'a Graph, queues: &'a mut LinkQueues, traffic: &'a mut TrafficStats, rng: &'a mut StdRng) -> Self {
 		Io {
-			graph: graph
+			graph,
+			queues,
+			traffic,
+			rng
 		}
 	}
 
-	pub fn link_iter(&self) -> IoIterator {
-		IoIterator::new(&self)
+	// random number generator of the simulation
+	pub fn rng(&mut self) -> &mut StdRng {
+		self.rng
 	}
 
-	pub fn nodes_count(&self) -> usize {
-		self.graph.node_count()
+	pub fn link_iter(&self) -> IoIterator<'a> {
+		IoIterator::new(self.graph)
 	}
 
 	pub fn node_links(&self, id: ID) -> &[Link] {
 		self.graph.get_neighbors(id)
 	}
 
+	// measured quality of a link (like a radio would report it)
+	pub fn link_quality(&self, from: ID, to: ID) -> Option<u16> {
+		self.graph.get_link(from, to).map(|link| link.quality())
+	}
+
+	// cost of a link according to the selected metric
+	pub fn link_cost(&self, from: ID, to: ID) -> Option<u16> {
+		self.graph.get_link(from, to).map(|link| link.cost())
+	}
+
+	// Send a management packet over a link.
+	// Returns false if the packet was lost.
+	pub fn send(&mut self, from: ID, to: ID, payload: Vec<u8>) -> bool {
+		if let Some(link) = self.graph.get_link(from, to) {
+			self.traffic.add(from, payload.len(), link.cost());
+		}
+		self.queues.send(self.graph, from, to, payload, self.rng)
+	}
+
+	// Send a management packet to all neighbors.
+	pub fn broadcast(&mut self, from: ID, payload: Vec<u8>) {
+		for link in self.graph.get_neighbors(from) {
+			self.traffic.add(from, payload.len(), link.cost());
+			self.queues.send(self.graph, from, link.to, payload.clone(), self.rng);
+		}
+	}
+
+	// Get all management packets that arrived at a node.
+	pub fn receive(&mut self, id: ID) -> Vec<Message> {
+		self.queues.receive(self.graph, id)
+	}
+
 /*
 	pub fn all_links(&self) -> &[Link] {
 		self.graph.links.as_slice()
@@ -380,14 +975,14 @@ impl<'a> Io<'a> {
 }
 
 pub struct IoIterator<'a> {
-	io : &'a Io<'a>,
+	graph : &'a Graph,
 	idx: usize,
 }
 
 impl<'a> IoIterator<'a> {
-	fn new(io: &'a Io) -> Self { 
+	fn new(graph: &'a Graph) -> Self {
 		Self {
-			io: io,
+			graph,
 			idx: 0,
 		}
 	}
@@ -399,7 +994,7 @@ impl<'a> Iterator for IoIterator<'a> {
 
 	// iterate over all links
 	fn next(&mut self) -> Option<Self::Item> {
-		let graph = &self.io.graph;
+		let graph = self.graph;
 		if self.idx < graph.link_count() {
 			let link = &graph.links[self.idx];
 			self.idx += 1;
@@ -409,3 +1004,78 @@ impl<'a> Iterator for IoIterator<'a> {
 		}
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn new_state() -> GlobalState {
+		let mut sim = GlobalState::new("127.0.0.1:8011");
+		sim.export_path.clear();
+		sim
+	}
+
+	fn degrees(graph: &Graph) -> Vec<usize> {
+		(0..graph.node_count() as ID).map(|id| graph.get_neighbors(id).len()).collect()
+	}
+
+	#[test]
+	fn gnp_edge_counts() {
+		let mut sim = new_state();
+		sim.add_gnp(20, 1.0);
+		assert_eq!(sim.graph.node_count(), 20);
+		assert_eq!(sim.graph.link_count(), 20 * 19);
+
+		let mut sim = new_state();
+		sim.add_gnp(20, 0.0);
+		assert_eq!(sim.graph.node_count(), 20);
+		assert_eq!(sim.graph.link_count(), 0);
+	}
+
+	#[test]
+	fn small_world_degrees() {
+		// without rewiring every node has k neighbors
+		let mut sim = new_state();
+		sim.add_small_world(30, 4, 0.0);
+		assert!(degrees(&sim.graph).iter().all(|&d| d == 4));
+
+		// rewiring keeps the number of links
+		let mut sim = new_state();
+		sim.add_small_world(30, 4, 0.5);
+		assert_eq!(sim.graph.link_count(), 30 * 4);
+	}
+
+	#[test]
+	fn scale_free_degrees() {
+		let (count, m) = (50, 3);
+		let mut sim = new_state();
+		sim.add_scale_free(count, m);
+
+		// initial clique and m links per further node, in both directions
+		let links = m * (m + 1) / 2 + (count - m - 1) * m;
+		assert_eq!(sim.graph.link_count() as u32, 2 * links);
+		assert!(degrees(&sim.graph).iter().all(|&d| d >= m as usize));
+	}
+
+	#[test]
+	fn random_geometric_edge_counts() {
+		// the radius covers the whole area
+		let mut sim = new_state();
+		sim.add_random_geometric(20, 1.5);
+		assert_eq!(sim.graph.link_count(), 20 * 19);
+
+		let mut sim = new_state();
+		sim.add_random_geometric(20, 0.0);
+		assert_eq!(sim.graph.link_count(), 0);
+	}
+
+	#[test]
+	fn generators_append_nodes() {
+		let mut sim = new_state();
+		sim.add_gnp(10, 1.0);
+		sim.add_scale_free(10, 2);
+		assert_eq!(sim.graph.node_count(), 20);
+		// no links between the two graphs
+		assert!((0..10).all(|id| sim.graph.get_neighbors(id).iter().all(|link| link.to < 10)));
+	}
+}
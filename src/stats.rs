@@ -1,54 +1,102 @@
+use crate::graph::ID;
 
-use crate::utils::CircularVec;
 
+// Management traffic counter
+#[derive(Clone, Copy, Default)]
+pub struct Traffic {
+	pub packets: u64,
+	pub bytes: u64,
+	pub costs: u64,
+}
+
+impl Traffic {
+	pub fn add(&mut self, bytes: usize, costs: u16) {
+		self.packets += 1;
+		self.bytes += bytes as u64;
+		self.costs += costs as u64;
+	}
 
-#[derive(Clone, PartialEq)]
-pub struct TestResult {
-	pub test_num: u32,
-	pub steps: u32,
-	pub test_packet_costs: u32,
-	pub comm_packet_costs: u32,
-	pub deployed_test_packets: u32,
-	pub received_test_packets: u32,
+	pub fn to_json(self) -> String {
+		format!("{{\"packets\": {}, \"bytes\": {}, \"costs\": {}}}", self.packets, self.bytes, self.costs)
+	}
+}
+
+/*
+ * Count management packets send through Io
+ * per (sending) node and per simulation step.
+ */
+pub struct TrafficStats {
+	pub total: Traffic,
+	pub nodes: Vec<Traffic>,
+	pub steps: Vec<Traffic>,
+	// simulation steps since the last clear
+	step: u32,
 }
 
-impl TestResult {
+impl TrafficStats {
 	pub fn new() -> Self {
 		Self {
-			test_num: 0,
-			steps: 0,
-			deployed_test_packets: 0,
-			received_test_packets: 0,
-			test_packet_costs: 0,
-			comm_packet_costs: 0,
+			total: Traffic::default(),
+			nodes: Vec::new(),
+			steps: Vec::new(),
+			step: 0,
 		}
 	}
-/*
-	pub fn add_costs(&mut self, packet: &Packet, link: &Link) {
-		let costs = link.cost() as u32;
-		if packet.is_test() {
-			self.test_packet_costs += costs;
-		} else {
-			self.comm_packet_costs += costs;
-		}
-	}
-*/
+
 	pub fn clear(&mut self) {
-		*self = TestResult::new();
+		*self = TrafficStats::new();
 	}
 
-	pub fn to_json(&self) -> String {
+	// count traffic of the following steps separately
+	pub fn next_step(&mut self) {
+		self.step += 1;
+	}
+
+	pub fn step_count(&self) -> u32 {
+		self.step
+	}
+
+	pub fn add(&mut self, from: ID, bytes: usize, costs: u16) {
+		let (from, step) = (from as usize, self.step as usize);
+
+		if from >= self.nodes.len() {
+			self.nodes.resize(from + 1, Traffic::default());
+		}
+
+		if step >= self.steps.len() {
+			self.steps.resize(step + 1, Traffic::default());
+		}
+
+		self.total.add(bytes, costs);
+		self.nodes[from].add(bytes, costs);
+		self.steps[step].add(bytes, costs);
+	}
+
+	pub fn to_json(&self, algorithm: &str) -> String {
 		use std::fmt::Write;
 		let mut ret = String::new();
 
 		write!(&mut ret, "{{").unwrap();
-		write!(&mut ret, "\"test_num\": {},", self.test_num).unwrap();
-		write!(&mut ret, "\"test_packet_costs\": {},", self.test_packet_costs).unwrap();
-		write!(&mut ret, "\"comm_packet_costs\": {},", self.comm_packet_costs).unwrap();
-		write!(&mut ret, "\"deployed_test_packets\": {},", self.deployed_test_packets).unwrap();
-		write!(&mut ret, "\"received_test_packets\": {},", self.received_test_packets).unwrap();
-		write!(&mut ret, "}}").unwrap();
-
-		return ret;
+		write!(&mut ret, "\"algorithm\": {},", serde_json::to_string(algorithm).unwrap()).unwrap();
+		write!(&mut ret, "\"total\": {},", self.total.to_json()).unwrap();
+
+		write!(&mut ret, "\"steps\": [").unwrap();
+		for (i, traffic) in self.steps.iter().enumerate() {
+			if i > 0 {
+				write!(&mut ret, ",").unwrap();
+			}
+			write!(&mut ret, "{}", traffic.to_json()).unwrap();
+		}
+
+		write!(&mut ret, "], \"nodes\": [").unwrap();
+		for (i, traffic) in self.nodes.iter().enumerate() {
+			if i > 0 {
+				write!(&mut ret, ",").unwrap();
+			}
+			write!(&mut ret, "{}", traffic.to_json()).unwrap();
+		}
+		write!(&mut ret, "]}}").unwrap();
+
+		ret
 	}
 }
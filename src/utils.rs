@@ -9,7 +9,7 @@ use std::io::prelude::*;
 use std::ops::{AddAssign, DivAssign, Index, Add, Mul, Div, Sub, Deref};
 use std::time::Duration;
 use std::default::Default;
-use rand;
+use rand::Rng;
 
 
 pub const RAD2DEG : f32 = 360.0 / (2.0 * f32::consts::PI);
@@ -17,6 +17,7 @@ pub const RAD2DEG : f32 = 360.0 / (2.0 * f32::consts::PI);
 pub const DEG2KM : f32 = 40076.0 / 360.0;
 
 
+#[derive(Debug)]
 pub struct MyError {
 	msg: String
 }
@@ -81,31 +82,62 @@ pub fn read_file(path: &str) -> Option<Vec<u8>> {
 	None
 }
 
+// append a byte to a packet payload
+pub fn write_u8(buf: &mut Vec<u8>, value: u8) {
+	buf.push(value);
+}
+
+// read a byte from a packet payload at pos
+pub fn read_u8(buf: &[u8], pos: &mut usize) -> Option<u8> {
+	if *pos < buf.len() {
+		*pos += 1;
+		Some(buf[*pos - 1])
+	} else {
+		None
+	}
+}
+
+// append a number to a packet payload
+pub fn write_u16(buf: &mut Vec<u8>, value: u16) {
+	buf.extend_from_slice(&value.to_be_bytes());
+}
+
+// read a number from a packet payload at pos
+pub fn read_u16(buf: &[u8], pos: &mut usize) -> Option<u16> {
+	if *pos + 2 <= buf.len() {
+		let value = u16::from_be_bytes([buf[*pos], buf[*pos + 1]]);
+		*pos += 2;
+		Some(value)
+	} else {
+		None
+	}
+}
+
+// append a number to a packet payload
+pub fn write_u32(buf: &mut Vec<u8>, value: u32) {
+	buf.extend_from_slice(&value.to_be_bytes());
+}
+
+// read a number from a packet payload at pos
+pub fn read_u32(buf: &[u8], pos: &mut usize) -> Option<u32> {
+	if *pos + 4 <= buf.len() {
+		let mut bytes = [0u8; 4];
+		bytes.copy_from_slice(&buf[*pos..*pos + 4]);
+		*pos += 4;
+		Some(u32::from_be_bytes(bytes))
+	} else {
+		None
+	}
+}
+
 pub fn fmt_duration(d: Duration) -> String {
 	let mss = d.subsec_millis() as u64;
 	let secs = d.as_secs();
 
-	// round up
-	fn round_div(x: u64, y: u64) -> u64 {
-		let d = x / y;
-		let remainder = x - d * y;
-		if (2 * remainder) >= y {
-			d + 1
-		} else {
-			d
-		}
-	}
-
-	let years = round_div(secs, 356 * 24 * 60 * 60);
-	let days = round_div(secs, 24 * 60 * 60)
-				- (years * 356);
-	let hours = round_div(secs, 60 * 60)
-				- (days * 24)
-				- (years * 356 * 24);
-	let mins = round_div(secs, 60)
-				- (hours * 60)
-				- (days * 24 * 60)
-				- (years * 356 * 24 * 60);
+	let years = secs / (356 * 24 * 60 * 60);
+	let days = (secs / (24 * 60 * 60)) % 356;
+	let hours = (secs / (60 * 60)) % 24;
+	let mins = (secs / 60) % 60;
 	let secs = secs % 60;
 
 	if years > 0 {
@@ -212,16 +244,16 @@ impl Vec3 {
 		self.data[2]
 	}
 
-	pub fn random_unit() -> Vec3 {
-		Vec3::random_in_area(1.0).unit()
+	pub fn random_unit<R: Rng>(rng: &mut R) -> Vec3 {
+		Vec3::random_in_area(1.0, rng).unit()
 	}
 
 	// random around in the box of (0, 0, 0)
-	pub fn random_in_area(r: f32) -> Vec3 {
+	pub fn random_in_area<R: Rng>(r: f32, rng: &mut R) -> Vec3 {
 		Vec3::new(
-			(2.0 * rand::random::<f32>() - 1.0) * r,
-			(2.0 * rand::random::<f32>() - 1.0) * r,
-			(2.0 * rand::random::<f32>() - 1.0) * r,
+			(2.0 * rng.gen::<f32>() - 1.0) * r,
+			(2.0 * rng.gen::<f32>() - 1.0) * r,
+			(2.0 * rng.gen::<f32>() - 1.0) * r,
 		)
 	}
 